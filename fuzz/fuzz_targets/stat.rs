@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use getpid::Stat;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let stat = Stat::parse(&text);
+    let _ = stat.tty_name();
+    if let Some(comm) = stat.comm {
+        // the name is always everything between the first ( and the last )
+        let open = text.find('(').unwrap();
+        let close = text.rfind(')').unwrap();
+        assert_eq!(comm, &text[open + 1..close]);
+    }
+});
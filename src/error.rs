@@ -0,0 +1,104 @@
+use std::io::Error as IoError;
+use std::num::ParseIntError;
+
+use regex::Error as RegexError;
+use serde_json::Error as JsonError;
+
+use super::Process;
+use time::format_timestamp;
+use walkdir::Error as WalkError;
+
+#[derive(Debug)]
+pub enum Error {
+    /// More than one process matched when only one was expected
+    Ambiguous(Vec<Process>),
+    /// The user said no when asked to confirm, holding what they declined
+    Declined(String),
+    /// Some user provided text, like a format name, could not be understood
+    Invalid(String),
+    Io(IoError),
+    Json(JsonError),
+    /// No process matched, holding what was being looked for
+    NotFound(String),
+    Other(String),
+    ParseInt(ParseIntError),
+    Regex(RegexError),
+    /// Gave up waiting, holding what was being waited for
+    Timeout(String),
+    Walk(WalkError),
+}
+
+use std::error::Error as STDError;
+impl ::std::fmt::Display for Error {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self {
+            Error::Ambiguous(processes) => write_ambiguous(processes, f),
+            Error::Declined(s) => write!(f, "{} was declined", s),
+            Error::Invalid(s) => s.fmt(f),
+            Error::Io(e) => e.fmt(f),
+            Error::Json(e) => e.fmt(f),
+            Error::NotFound(s) => write!(f, "no process found for {}", s),
+            Error::Other(s) => s.fmt(f),
+            Error::ParseInt(e) => e.fmt(f),
+            Error::Regex(e) => e.fmt(f),
+            Error::Timeout(s) => write!(f, "timed out waiting for {}", s),
+            Error::Walk(e) => e.fmt(f),
+        }
+    }
+}
+
+fn write_ambiguous(processes: &[Process], f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+    writeln!(f, "{} processes matched:", processes.len())?;
+    write!(f, "{:>8}  {:<10}  {:<19}  COMMAND", "PID", "USER", "STARTED")?;
+    for p in processes {
+        let user = p.user.clone()
+            .or_else(|| p.uid.map(|u| u.to_string()))
+            .unwrap_or_else(|| "?".to_string());
+        let started = p.started_at.map(format_timestamp).unwrap_or_else(|| "?".to_string());
+        write!(f, "\n{:>8}  {:<10}  {:<19}  {}", p.pid, user, started, p.display_command())?;
+    }
+    Ok(())
+}
+
+impl STDError for Error {
+    fn source(&self) -> Option<&(dyn STDError + 'static)> {
+        match self {
+            Error::Io(ref e) => Some(e),
+            Error::Json(ref e) => Some(e),
+            Error::ParseInt(ref e) => Some(e),
+            Error::Regex(ref e) => Some(e),
+            Error::Walk(ref e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<IoError> for Error {
+    fn from(other: IoError) -> Self {
+        Error::Io(other)
+    }
+}
+
+impl From<JsonError> for Error {
+    fn from(other: JsonError) -> Self {
+        Error::Json(other)
+    }
+}
+
+impl From<WalkError> for Error {
+    fn from(other: WalkError) -> Self {
+        Error::Walk(other)
+    }
+}
+
+impl From<ParseIntError> for Error {
+    fn from(other: ParseIntError) -> Self {
+        Error::ParseInt(other)
+    }
+}
+
+impl From<RegexError> for Error {
+    fn from(other: RegexError) -> Self {
+        Error::Regex(other)
+    }
+}
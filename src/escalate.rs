@@ -0,0 +1,139 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use super::Error;
+use handle::{Handle, Identity, POLL_INTERVAL};
+use signal::{Delivery, Signal};
+use time::parse_duration;
+
+/// How long to wait after a step that doesn't say
+const DEFAULT_WAIT: Duration = Duration::from_secs(5);
+
+/// One signal to send and how long to wait for it to work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub signal: Signal,
+    pub wait: Duration,
+}
+
+/// A list of signals to send in turn until a process exits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escalation {
+    steps: Vec<Step>,
+}
+
+impl Escalation {
+    pub fn new(steps: Vec<Step>) -> Escalation {
+        Escalation { steps }
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Signal each process in `identities` with each step in turn, moving
+    /// on once the step's time is up and stopping once they have all exited
+    ///
+    /// The result is in the same order as `identities`
+    pub fn run(&self, identities: &[Identity]) -> Result<Vec<Stopped>, Error> {
+        let mut results = vec![Stopped::Survived; identities.len()];
+        let mut pending = vec![];
+        for (i, &identity) in identities.iter().enumerate() {
+            match Handle::open(identity)? {
+                Some(handle) => pending.push((i, handle)),
+                None => results[i] = Stopped::AlreadyGone,
+            }
+        }
+        let mut previous = None;
+        for step in &self.steps {
+            let mut signalled = vec![];
+            for (i, handle) in pending {
+                match handle.signal(step.signal)? {
+                    Delivery::Sent => signalled.push((i, handle)),
+                    // it exited after the last step's time was up
+                    Delivery::NoSuchProcess => results[i] = previous.map(Stopped::By).unwrap_or(Stopped::AlreadyGone),
+                    Delivery::NotPermitted => results[i] = Stopped::NotPermitted(step.signal),
+                }
+            }
+            pending = wait_all(signalled, step.wait, |i| results[i] = Stopped::By(step.signal))?;
+            if pending.is_empty() {
+                break;
+            }
+            previous = Some(step.signal);
+        }
+        Ok(results)
+    }
+}
+
+/// Wait up to `timeout` for every handle to exit, calling `exited` with
+/// the index of each one that does and returning the rest
+fn wait_all<F>(mut pending: Vec<(usize, Handle)>, timeout: Duration, mut exited: F) -> Result<Vec<(usize, Handle)>, Error>
+where F: FnMut(usize) {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let mut running = vec![];
+        for (i, handle) in pending {
+            if handle.has_exited()? {
+                exited(i);
+            } else {
+                running.push((i, handle));
+            }
+        }
+        pending = running;
+        let now = Instant::now();
+        if pending.is_empty() || now >= deadline {
+            return Ok(pending);
+        }
+        sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+impl FromStr for Escalation {
+    type Err = Error;
+    /// Parse a comma separated list of steps like `TERM:10s,INT:5s,KILL`,
+    /// a step without a time waits 5 seconds
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let steps = s.split(',')
+            .filter(|s| !s.is_empty())
+            .map(|step| {
+                let mut parts = step.splitn(2, ':');
+                let signal = parts.next().unwrap_or_default().parse()?;
+                let wait = match parts.next() {
+                    Some(wait) => parse_duration(wait)?,
+                    None => DEFAULT_WAIT,
+                };
+                Ok(Step { signal, wait })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if steps.is_empty() {
+            return Err(Error::Invalid(format!("no signals in {}", s)));
+        }
+        Ok(Escalation { steps })
+    }
+}
+
+/// What stopped a process during an `Escalation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stopped {
+    /// It exited after this signal
+    By(Signal),
+    /// It had exited before the first signal was sent
+    AlreadyGone,
+    /// We aren't allowed to send it this signal
+    NotPermitted(Signal),
+    /// It was still running after the last step
+    Survived,
+}
+
+impl Display for Stopped {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Stopped::By(signal) => write!(f, "stopped by {}", signal),
+            Stopped::AlreadyGone => "already exited".fmt(f),
+            Stopped::NotPermitted(signal) => write!(f, "EPERM sending {}", signal),
+            Stopped::Survived => "still running".fmt(f),
+        }
+    }
+}
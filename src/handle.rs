@@ -0,0 +1,209 @@
+use std::io::Error as IoError;
+#[cfg(target_os = "linux")]
+use std::ptr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use libc::{self, c_int, c_long, pid_t};
+
+use super::{Error, Process};
+use signal::{Delivery, Signal};
+#[cfg(target_os = "linux")]
+use stat::Stat;
+
+/// A pid together with the time its process started
+///
+/// Pids are reused once a process exits, the pair is not, so this
+/// is what should be checked before acting on a process found earlier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Identity {
+    pub pid: usize,
+    /// The time the process started, in clock ticks since boot
+    pub start_time: Option<u64>,
+}
+
+impl Identity {
+    /// Check that `pid` still belongs to this process and that it
+    /// hasn't exited
+    ///
+    /// Without a start time only the pid can be checked
+    #[cfg(target_os = "linux")]
+    pub fn is_running(&self) -> bool {
+        let stat = match Stat::read(format!("/proc/{}/stat", self.pid)) {
+            Ok(stat) => stat,
+            Err(_) => return false,
+        };
+        // a zombie has exited, it just hasn't been reaped yet
+        if stat.state == Some('Z') || stat.state == Some('X') {
+            return false;
+        }
+        self.start_time.is_none() || stat.starttime == self.start_time
+    }
+
+    /// Check that `pid` still belongs to a process that hasn't exited
+    #[cfg(not(target_os = "linux"))]
+    pub fn is_running(&self) -> bool {
+        unsafe { libc::kill(self.pid as pid_t, 0) == 0 }
+            || IoError::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+}
+
+impl Process {
+    /// The pid and start time that together identify this process
+    pub fn identity(&self) -> Identity {
+        Identity {
+            pid: self.pid,
+            start_time: self.start_time,
+        }
+    }
+}
+
+/// A process that has been checked against its `Identity`
+///
+/// Where the kernel supports it this holds a pidfd, which keeps
+/// referring to the same process even after its pid is reused.
+/// Otherwise the identity is checked again before every action.
+#[derive(Debug)]
+pub struct Handle {
+    identity: Identity,
+    pidfd: Option<c_int>,
+}
+
+impl Handle {
+    /// Open a handle on the process with `identity`, `None` means it
+    /// has exited or its pid now belongs to another process
+    pub fn open(identity: Identity) -> Result<Option<Handle>, Error> {
+        if identity.pid == 0 || identity.pid > pid_t::MAX as usize {
+            return Err(Error::Invalid(format!("invalid pid {}", identity.pid)));
+        }
+        let pidfd = match pidfd_open(identity.pid as pid_t) {
+            Ok(fd) => fd,
+            Err(ref e) if e.raw_os_error() == Some(libc::ESRCH) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let handle = Handle { identity, pidfd };
+        // checked after opening, so the pidfd can't be for a
+        // process that took over the pid in between
+        if !identity.is_running() {
+            return Ok(None);
+        }
+        Ok(Some(handle))
+    }
+
+    pub fn identity(&self) -> Identity {
+        self.identity
+    }
+
+    /// Send `signal` to the process
+    pub fn signal(&self, signal: Signal) -> Result<Delivery, Error> {
+        let ret = match self.pidfd {
+            Some(fd) => pidfd_send_signal(fd, signal.number()),
+            None => {
+                if !self.identity.is_running() {
+                    return Ok(Delivery::NoSuchProcess);
+                }
+                c_long::from(unsafe { libc::kill(self.identity.pid as pid_t, signal.number()) })
+            }
+        };
+        if ret == 0 {
+            return Ok(Delivery::Sent);
+        }
+        let e = IoError::last_os_error();
+        match e.raw_os_error() {
+            Some(libc::EPERM) => Ok(Delivery::NotPermitted),
+            Some(libc::ESRCH) => Ok(Delivery::NoSuchProcess),
+            _ => Err(e.into()),
+        }
+    }
+
+    /// Check whether the process has exited, without waiting
+    pub fn has_exited(&self) -> Result<bool, Error> {
+        match self.pidfd {
+            Some(fd) => poll_pidfd(fd, 0),
+            None => Ok(!self.identity.is_running()),
+        }
+    }
+
+    /// Wait up to `timeout`, or forever if there isn't one, for the
+    /// process to exit, returning whether it did
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<bool, Error> {
+        let deadline = timeout.and_then(|t| Instant::now().checked_add(t));
+        loop {
+            let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            if let Some(fd) = self.pidfd {
+                // poll can only wait so long, so it may take a few goes
+                let ms = remaining.map(|r| r.as_millis().min(c_int::MAX as u128) as c_int).unwrap_or(-1);
+                if poll_pidfd(fd, ms)? {
+                    return Ok(true);
+                }
+            } else if self.has_exited()? {
+                return Ok(true);
+            }
+            match remaining {
+                Some(r) if r == Duration::from_secs(0) => return Ok(false),
+                Some(r) if self.pidfd.is_none() => sleep(POLL_INTERVAL.min(r)),
+                None if self.pidfd.is_none() => sleep(POLL_INTERVAL),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// How often to check on a process that has no pidfd
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A pidfd becomes readable once its process exits
+fn poll_pidfd(fd: c_int, timeout_ms: c_int) -> Result<bool, Error> {
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    loop {
+        let ret = unsafe { libc::poll(&mut pfd, 1, timeout_ms) };
+        if ret >= 0 {
+            return Ok(ret > 0);
+        }
+        let e = IoError::last_os_error();
+        if e.raw_os_error() != Some(libc::EINTR) {
+            return Err(e.into());
+        }
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        if let Some(fd) = self.pidfd {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+/// Open a pidfd for `pid`, `None` if the kernel is too old to have them
+#[cfg(target_os = "linux")]
+fn pidfd_open(pid: pid_t) -> Result<Option<c_int>, IoError> {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if fd >= 0 {
+        return Ok(Some(fd as c_int));
+    }
+    let e = IoError::last_os_error();
+    match e.raw_os_error() {
+        Some(libc::ENOSYS) => Ok(None),
+        _ => Err(e),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pidfd_open(_pid: pid_t) -> Result<Option<c_int>, IoError> {
+    Ok(None)
+}
+
+#[cfg(target_os = "linux")]
+fn pidfd_send_signal(fd: c_int, signal: c_int) -> c_long {
+    unsafe { libc::syscall(libc::SYS_pidfd_send_signal, fd, signal, ptr::null::<libc::siginfo_t>(), 0) }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pidfd_send_signal(_fd: c_int, _signal: c_int) -> c_long {
+    unreachable!("pidfds are only opened on linux")
+}
@@ -0,0 +1,229 @@
+use std::collections::HashSet;
+use std::fs::{canonicalize, metadata, read_to_string};
+use std::io::ErrorKind;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use super::{Error, Field, Process};
+use procfs::{get_fd_links, get_link_for};
+
+/// How a process is holding on to a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Use {
+    /// An open file descriptor
+    Fd,
+    /// The working directory
+    Cwd,
+    /// The root directory, set by `chroot`
+    Root,
+    /// A memory mapped file, like a shared library
+    Map,
+}
+
+/// A file a process is holding on to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Held {
+    #[serde(rename = "use")]
+    pub how: Use,
+    /// The file descriptor, for `Use::Fd`
+    pub fd: Option<u32>,
+    /// Where the file was when it was opened
+    pub path: String,
+    /// The file's `st_dev`, or for `Use::Map` the device of
+    /// its file system, see `Target::matches_fs_file`
+    pub device: u64,
+    pub inode: u64,
+}
+
+/// A file to look for, or with `mount` anything on the same file system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    device: u64,
+    /// The device of the file system as a whole, which `maps` and
+    /// `/proc/locks` report instead of `st_dev`. These differ for
+    /// btrfs subvolumes, which each get a device of their own
+    fs_device: u64,
+    inode: u64,
+    mount: bool,
+}
+
+impl Target {
+    pub fn new<P: AsRef<Path>>(path: P, mount: bool) -> Result<Target, Error> {
+        let path = path.as_ref();
+        let meta = metadata(path).map_err(|e| Error::Invalid(format!("{}: {}", path.display(), e)))?;
+        let fs_device = canonicalize(path).ok()
+            .and_then(|path| mount_device(&path))
+            .unwrap_or_else(|| meta.dev());
+        Ok(Target {
+            device: meta.dev(),
+            fs_device,
+            inode: meta.ino(),
+            mount,
+        })
+    }
+
+    pub fn matches(&self, held: &Held) -> bool {
+        match held.how {
+            Use::Map => self.matches_fs_file(held.device, held.inode),
+            Use::Fd | Use::Cwd | Use::Root => self.matches_file(held.device, held.inode),
+        }
+    }
+
+    /// Check a file by its device and inode numbers
+    pub fn matches_file(&self, device: u64, inode: u64) -> bool {
+        device == self.device && (self.mount || inode == self.inode)
+    }
+
+    /// Check a file by the device of its file system, like the
+    /// entries of `/proc/<pid>/maps` and `/proc/locks`, and its inode
+    pub fn matches_fs_file(&self, fs_device: u64, inode: u64) -> bool {
+        fs_device == self.fs_device && (self.mount || inode == self.inode)
+    }
+}
+
+/// The device `/proc/self/mountinfo` lists for the mount holding
+/// `path`, which should have all its symlinks resolved
+fn mount_device(path: &Path) -> Option<u64> {
+    let mountinfo = read_to_string("/proc/self/mountinfo").ok()?;
+    let mut best: Option<(usize, u64)> = None;
+    for line in mountinfo.lines() {
+        let fields: Vec<&str> = line.split(' ').collect();
+        let (dev, mount_point) = match (fields.get(2), fields.get(4)) {
+            (Some(dev), Some(mount_point)) => (dev, unescape_mount(mount_point)),
+            _ => continue,
+        };
+        if !path.starts_with(&mount_point) {
+            continue;
+        }
+        let mut parts = dev.splitn(2, ':');
+        let major = parts.next().and_then(|m| m.parse().ok());
+        let minor = parts.next().and_then(|m| m.parse().ok());
+        if let (Some(major), Some(minor)) = (major, minor) {
+            let depth = Path::new(&mount_point).components().count();
+            // later mounts on the same point hide the earlier ones
+            if best.map(|(d, _)| depth >= d).unwrap_or(true) {
+                best = Some((depth, makedev(major, minor)));
+            }
+        }
+    }
+    best.map(|(_, device)| device)
+}
+
+/// Undo the octal escapes mountinfo uses for spaces, tabs,
+/// newlines and backslashes in paths
+fn unescape_mount(text: &str) -> String {
+    let mut ret = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find('\\') {
+        ret.push_str(&rest[..i]);
+        match rest.get(i + 1..i + 4).and_then(|o| u8::from_str_radix(o, 8).ok()) {
+            Some(byte) => {
+                ret.push(byte as char);
+                rest = &rest[i + 4..];
+            }
+            None => {
+                ret.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    ret.push_str(rest);
+    ret
+}
+
+/// Fill in `holding` on each of `processes` that has `target` open
+/// as a file descriptor, working directory, root or memory map
+pub fn attach_holding(processes: &mut [Process], proc_root: &Path, target: &Target) -> Result<(), Error> {
+    for p in processes {
+        let base = proc_root.join(p.pid.to_string());
+        let mut held = vec![];
+        let mut denied = false;
+        for &(name, how) in &[("cwd", Use::Cwd), ("root", Use::Root)] {
+            match get_held(&base.join(name), how) {
+                Ok(h) => held.push(h),
+                Err(ref e) if e.kind() == ErrorKind::PermissionDenied => denied = true,
+                Err(_) => (),
+            }
+        }
+        match get_fd_links(proc_root, p.pid) {
+            Ok(links) => {
+                for link in links {
+                    if let Ok(meta) = metadata(&link.path) {
+                        held.push(Held {
+                            how: Use::Fd,
+                            fd: Some(link.fd),
+                            path: link.target,
+                            device: meta.dev(),
+                            inode: meta.ino(),
+                        });
+                    }
+                }
+            }
+            Err(ref e) if e.kind() == ErrorKind::PermissionDenied => denied = true,
+            Err(_) => (),
+        }
+        match read_to_string(base.join("maps")) {
+            Ok(maps) => held.extend(parse_maps(&maps)),
+            Err(ref e) if e.kind() == ErrorKind::PermissionDenied => denied = true,
+            Err(_) => (),
+        }
+        if denied {
+            p.hidden.push(Field::Holding);
+        }
+        held.retain(|h| target.matches(h));
+        if !held.is_empty() {
+            p.holding = Some(held);
+        }
+    }
+    Ok(())
+}
+
+/// Look at what the `cwd` or `root` link in `/proc/<pid>` points to,
+/// these can be followed even when the directory has been deleted
+fn get_held(link: &Path, how: Use) -> Result<Held, ::std::io::Error> {
+    let path = get_link_for(&link.to_string_lossy(), true)?;
+    let meta = metadata(link)?;
+    Ok(Held {
+        how,
+        fd: None,
+        path,
+        device: meta.dev(),
+        inode: meta.ino(),
+    })
+}
+
+/// Parse the text of `/proc/<pid>/maps`, keeping one entry for each
+/// file no matter how many times it is mapped
+pub fn parse_maps(text: &str) -> Vec<Held> {
+    let mut seen = HashSet::new();
+    text.lines().filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let dev = fields.nth(3)?;
+        let inode: u64 = fields.next()?.parse().ok()?;
+        // anonymous memory, the heap and the stack have no inode
+        if inode == 0 {
+            return None;
+        }
+        let path: Vec<&str> = fields.collect();
+        let mut parts = dev.splitn(2, ':');
+        let major = u64::from_str_radix(parts.next()?, 16).ok()?;
+        let minor = u64::from_str_radix(parts.next()?, 16).ok()?;
+        let device = makedev(major, minor);
+        if !seen.insert((device, inode)) {
+            return None;
+        }
+        Some(Held {
+            how: Use::Map,
+            fd: None,
+            path: path.join(" "),
+            device,
+            inode,
+        })
+    }).collect()
+}
+
+/// Combine a major and minor device number the way `st_dev` does
+pub(crate) fn makedev(major: u64, minor: u64) -> u64 {
+    (minor & 0xff) | ((major & 0xfff) << 8) | ((minor & !0xff) << 12) | ((major & !0xfff) << 32)
+}
@@ -0,0 +1,211 @@
+extern crate libc;
+extern crate regex;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate walkdir;
+
+mod error;
+mod escalate;
+mod handle;
+mod holding;
+#[cfg(target_os = "macos")]
+mod mac;
+mod locks;
+mod matcher;
+mod net;
+#[cfg(target_os = "linux")]
+mod netlink;
+mod output;
+mod procfs;
+mod select;
+mod signal;
+mod source;
+pub mod stat;
+#[cfg(target_os = "macos")]
+mod sysctl;
+mod template;
+mod time;
+mod tree;
+mod unix_socket;
+mod users;
+mod watch;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use template::escape_control;
+
+pub use error::Error;
+pub use escalate::{Escalation, Step, Stopped};
+pub use handle::{Handle, Identity};
+pub use holding::{attach_holding, parse_maps, Held, Target, Use};
+pub use locks::{attach_locks, parse_locks, Lock, LockKind, LockMode};
+pub use matcher::{ArgPattern, EnvPattern, Match, Matcher, Mode, NameSource, Pattern};
+pub use net::{attach_listening, listening_sockets, parse_sockets, Port, Protocol, Socket};
+#[cfg(target_os = "linux")]
+pub use netlink::ProcConnector;
+pub use procfs::attach_environ;
+pub use output::{write_processes, Format};
+pub use select::{select, Selection};
+pub use signal::{send_signal, Delivery, Signal};
+pub use stat::Stat;
+pub use source::{native_source, Fixture, InMemory, ProcFs, ProcessSource};
+#[cfg(target_os = "macos")]
+pub use source::Sysctl;
+pub use template::Template;
+pub use time::parse_duration;
+pub use tree::{write_trees, Tree, TreeFormat};
+pub use unix_socket::{attach_unix_sockets, parse_unix, Role, UnixEntry, UnixSocket, UnixTarget};
+pub use users::Accounts;
+pub use watch::{Event, EventKind, Snapshot};
+
+/// A single running process
+///
+/// Any field other than `pid` will be `None` if it could not be read,
+/// either because the OS doesn't provide it or because we don't have
+/// permission to see it. Fields in the latter case are also listed
+/// in `hidden`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Process {
+    pub pid: usize,
+    /// The executable name as reported by the OS
+    pub cmd: Option<String>,
+    /// The resolved path to the executable
+    pub full_cmd_path: Option<String>,
+    /// The first command line argument, usually the name
+    /// the executable was invoked as
+    pub arg0: Option<String>,
+    /// The command line arguments, not including the executable
+    pub args: Option<Vec<String>>,
+    /// The real user id of the process' owner
+    pub uid: Option<u32>,
+    /// The effective user id, used for most permission checks
+    pub euid: Option<u32>,
+    /// The saved set-user-id
+    pub suid: Option<u32>,
+    /// The name of the user with the real user id
+    pub user: Option<String>,
+    /// The real group id
+    pub gid: Option<u32>,
+    /// The effective group id
+    pub egid: Option<u32>,
+    /// The saved set-group-id
+    pub sgid: Option<u32>,
+    /// The name of the group with the real group id
+    pub group: Option<String>,
+    /// The pid of the parent process
+    pub ppid: Option<usize>,
+    /// The process group id
+    pub pgid: Option<usize>,
+    /// The session id
+    pub sid: Option<usize>,
+    /// The controlling terminal, like `pts/0` or `tty1`
+    pub tty: Option<String>,
+    /// The scheduling state, like `R` for running, `S` for sleeping
+    /// or `Z` for a zombie that has exited but not been reaped
+    pub state: Option<char>,
+    /// The time the process started, in clock ticks since boot
+    pub start_time: Option<u64>,
+    /// The time the process started, in seconds since the unix epoch
+    pub started_at: Option<u64>,
+    /// The sockets bound to the port that was looked up
+    pub listening: Option<Vec<Socket>>,
+    /// The ways this process is holding the file that was looked up
+    pub holding: Option<Vec<Held>>,
+    /// The unix sockets this process has for the path that was looked up
+    pub unix_sockets: Option<Vec<UnixSocket>>,
+    /// The locks this process holds or is waiting for on the
+    /// file that was looked up
+    pub locks: Option<Vec<Lock>>,
+    /// The environment the process was started with, only read
+    /// when asked for
+    pub env: Option<BTreeMap<String, String>>,
+    /// The fields we were denied permission to read
+    pub hidden: Vec<Field>,
+}
+
+impl Process {
+    /// Whether the process has exited, even if its parent
+    /// hasn't collected its exit status yet
+    pub fn has_exited(&self) -> bool {
+        self.state == Some('Z') || self.state == Some('X')
+    }
+
+    /// The command line for showing to people, falling back to
+    /// `[cmd]` for processes without one, like kernel threads
+    ///
+    /// Control characters are escaped so an argument containing a
+    /// newline can't break up a table or tree
+    pub fn display_command(&self) -> String {
+        let command = self.command_line()
+            .or_else(|| self.cmd.as_ref().map(|c| format!("[{}]", c)))
+            .unwrap_or_else(|| "?".to_string());
+        escape_control(&command)
+    }
+}
+
+/// One of the optional fields of a `Process`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Cmd,
+    FullCmdPath,
+    Args,
+    Listening,
+    Holding,
+    UnixSockets,
+    Locks,
+    Env,
+}
+
+/// Get a list of every process currently running
+pub fn get_processes() -> Result<Vec<Process>, Error> {
+    native_source().processes()
+}
+
+/// Get a list of every process in a directory laid out like `/proc`
+///
+/// This could be the real `/proc`, the host's `/proc` mounted
+/// into a container or a copy taken from another machine
+pub fn get_processes_in<P: AsRef<Path>>(proc_root: P) -> Result<Vec<Process>, Error> {
+    ProcFs::new(proc_root.as_ref()).processes()
+}
+
+/// Get every running process whose executable name is exactly `name`
+pub fn get_by_name(name: &str) -> Result<Vec<Process>, Error> {
+    let processes = get_processes()?;
+    Ok(processes.into_iter().filter(|p| p.cmd.as_ref().map(|c| c == name).unwrap_or(false)).collect())
+}
+
+/// Get every running process where any of its names is exactly `name`
+///
+/// Unlike `get_by_name` this will also match the file name of the executable
+/// and of `arg0`, so it can find processes whose name is longer than the
+/// OS will report. Each match notes which of the names matched.
+pub fn find_by_name(name: &str) -> Result<Vec<Match>, Error> {
+    let pattern = Pattern::new(name, Mode::Exact, false)?;
+    find(&Matcher::new(pattern).resolve(true))
+}
+
+/// Get every running process accepted by `matcher`
+pub fn find(matcher: &Matcher) -> Result<Vec<Match>, Error> {
+    find_in(&native_source(), matcher)
+}
+
+/// Get every process from `source` accepted by `matcher`
+pub fn find_in<S: ProcessSource + ?Sized>(source: &S, matcher: &Matcher) -> Result<Vec<Match>, Error> {
+    let processes = processes_for(source, matcher)?;
+    Ok(processes.into_iter().filter_map(|p| matcher.check(p)).collect())
+}
+
+/// Get every process from `source` with whatever `matcher` needs to
+/// check them attached, without leaving out the ones it doesn't accept
+pub fn processes_for<S: ProcessSource + ?Sized>(source: &S, matcher: &Matcher) -> Result<Vec<Process>, Error> {
+    let mut processes = source.processes()?;
+    if let Some(root) = source.proc_root() {
+        matcher.attach(&mut processes, root)?;
+    }
+    Ok(processes)
+}
@@ -0,0 +1,156 @@
+use std::fs::{read_dir, read_to_string};
+use std::io::{Error as IoError, ErrorKind};
+use std::path::Path;
+
+use super::{Error, Field, Process};
+use holding::{makedev, Target};
+
+/// Which API took a lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LockKind {
+    /// `fcntl` record locks, owned by the process
+    Posix,
+    /// `flock` locks, owned by the open file
+    Flock,
+    /// `fcntl` open file description locks
+    Ofd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LockMode {
+    Read,
+    Write,
+}
+
+/// One line of `/proc/locks`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Lock {
+    pub kind: LockKind,
+    pub mode: LockMode,
+    pub mandatory: bool,
+    /// The process is waiting for the lock rather than holding it
+    pub waiting: bool,
+    pub pid: usize,
+    /// The device of the file system rather than the file's `st_dev`,
+    /// see `Target::matches_fs_file`
+    pub device: u64,
+    pub inode: u64,
+    /// The first byte locked
+    pub start: u64,
+    /// The last byte locked, `None` means the end of the file
+    pub end: Option<u64>,
+}
+
+/// Parse the text of `/proc/locks`, lines that can't be read and
+/// locks without an owning process are skipped
+///
+/// Open file description locks are always listed with a pid of -1, and
+/// `flock` locks with the pid that took them even though they belong to
+/// the open file, use `attach_locks` to find who actually holds them
+pub fn parse_locks(text: &str) -> Vec<Lock> {
+    text.lines()
+        .filter_map(parse_line)
+        .filter(|&(pid, _)| pid > 0)
+        .map(|(_, lock)| lock)
+        .collect()
+}
+
+/// Parse one numbered lock, returning the pid as written and the
+/// lock with `pid` set to it or to 0 if there is none
+fn parse_line(line: &str) -> Option<(i64, Lock)> {
+    let mut fields: Vec<&str> = line.split_whitespace().skip(1).collect();
+    let waiting = fields.first() == Some(&"->");
+    if waiting {
+        fields.remove(0);
+    }
+    let kind = match *fields.first()? {
+        "POSIX" => LockKind::Posix,
+        "FLOCK" => LockKind::Flock,
+        "OFDLCK" => LockKind::Ofd,
+        _ => return None,
+    };
+    let mode = match *fields.get(2)? {
+        "READ" => LockMode::Read,
+        "WRITE" => LockMode::Write,
+        _ => return None,
+    };
+    let pid: i64 = fields.get(3)?.parse().ok()?;
+    let mut file = fields.get(4)?.splitn(3, ':');
+    let major = u64::from_str_radix(file.next()?, 16).ok()?;
+    let minor = u64::from_str_radix(file.next()?, 16).ok()?;
+    let inode = file.next()?.parse().ok()?;
+    Some((pid, Lock {
+        kind,
+        mode,
+        mandatory: *fields.get(1)? == "MANDATORY",
+        waiting,
+        pid: pid.max(0) as usize,
+        device: makedev(major, minor),
+        inode,
+        start: fields.get(5)?.parse().ok()?,
+        end: fields.get(6)?.parse().ok(),
+    }))
+}
+
+/// Fill in `locks` on each of `processes` that holds, or is waiting
+/// for, a lock on `target`, read from a directory laid out like `/proc`
+///
+/// `fcntl` locks and anything being waited for are found through
+/// `locks`. `flock` and open file description locks belong to an open
+/// file, which can be shared between processes, so their holders are
+/// found by looking for them in the `fdinfo` of each process. Waiting
+/// open file description locks have no pid and can't be found
+pub fn attach_locks(processes: &mut [Process], proc_root: &Path, target: &Target) -> Result<(), Error> {
+    let text = read_to_string(proc_root.join("locks"))?;
+    let all: Vec<(i64, Lock)> = text.lines()
+        .filter_map(parse_line)
+        .filter(|(_, l)| target.matches_fs_file(l.device, l.inode))
+        .collect();
+    let by_pid: Vec<&Lock> = all.iter()
+        .filter(|&&(pid, ref l)| pid > 0 && (l.kind == LockKind::Posix || l.waiting))
+        .map(|(_, l)| l)
+        .collect();
+    let by_file = all.iter().any(|(_, l)| l.kind != LockKind::Posix && !l.waiting);
+    for p in processes {
+        let mut held: Vec<Lock> = by_pid.iter().filter(|l| l.pid == p.pid).map(|&l| l.clone()).collect();
+        if by_file {
+            match fd_locks(proc_root, p.pid) {
+                Ok(locks) => {
+                    for mut lock in locks {
+                        lock.pid = p.pid;
+                        // every fd sharing an open file lists its locks
+                        if lock.kind != LockKind::Posix && target.matches_fs_file(lock.device, lock.inode) && !held.contains(&lock) {
+                            held.push(lock);
+                        }
+                    }
+                }
+                Err(ref e) if e.kind() == ErrorKind::PermissionDenied => p.hidden.push(Field::Locks),
+                Err(_) => (),
+            }
+        }
+        if !held.is_empty() {
+            p.locks = Some(held);
+        }
+    }
+    Ok(())
+}
+
+/// The locks listed on the `lock:` lines of every file in the
+/// `fdinfo` directory of `pid`
+fn fd_locks(proc_root: &Path, pid: usize) -> Result<Vec<Lock>, IoError> {
+    let mut ret = vec![];
+    for entry in read_dir(proc_root.join(pid.to_string()).join("fdinfo"))? {
+        let text = match read_to_string(entry?.path()) {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+        for line in text.lines() {
+            if let Some(lock) = line.strip_prefix("lock:") {
+                ret.extend(parse_line(lock).map(|(_, l)| l));
+            }
+        }
+    }
+    Ok(ret)
+}
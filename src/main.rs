@@ -1,187 +1,752 @@
 extern crate docopt;
+extern crate getpid;
+extern crate libc;
 extern crate serde;
+extern crate serde_json;
 #[macro_use]
 extern crate serde_derive;
-#[cfg(not(target_os = "macos"))]
-extern crate walkdir;
-#[cfg(target_os="macos")]
-extern crate libc;
 
-#[cfg(target_os = "macos")]
-mod mac;
+use docopt::Docopt;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime};
 
-use std::io::Error as IoError;
-#[cfg(not(target_os = "macos"))]
-use std::fs::{read_link, read_to_string};
-
-use docopt::{Docopt,Error as DocError};
-#[cfg(not(target_os = "macos"))]
-use walkdir::{WalkDir,Error as WalkError};
-#[cfg(target_os = "macos")]
-mod sysctl;
+use getpid::{attach_environ, native_source, parse_duration, processes_for, select, send_signal, write_processes, write_trees, Accounts, ArgPattern, Delivery, EnvPattern, Error, Escalation, Format, Handle, Match, Matcher, Mode, Pattern, ProcFs, Process, ProcessSource, Selection, Signal, Snapshot, Stopped, Target, Template, Tree, TreeFormat, UnixTarget};
+#[cfg(target_os = "linux")]
+use getpid::ProcConnector;
 
 static HELP: &str = "
 GET PID a tool for getting a pid for a running process.DocError
 
 Usage:
-    getpid <name>
+    getpid watch [options] [--contains|--glob|--regex] [--arg=<arg>...] [--env=<env>...] [<name>]
+    getpid tree [options] [--contains|--glob|--regex] [--all|--newest|--oldest|--first] [--arg=<arg>...] [--env=<env>...] [<name>]
+    getpid ancestors [options] [--contains|--glob|--regex] [--all|--newest|--oldest|--first] [--arg=<arg>...] [--env=<env>...] [<name>]
+    getpid kill [options] [--contains|--glob|--regex] [--all|--newest|--oldest|--first] [--arg=<arg>...] [--env=<env>...] [<name>]
+    getpid wait (--appear|--exit) [options] [--contains|--glob|--regex] [--all|--newest|--oldest|--first] [--arg=<arg>...] [--env=<env>...] [<name>]
+    getpid [options] [--contains|--glob|--regex] [--all|--newest|--oldest|--first] [--arg=<arg>...] [--env=<env>...] [<name>]
     getpid [--help|-h]
 
+Commands:
+    tree               print each matching process and everything it started
+    ancestors          print each matching process and its parents up to pid 1
+    kill               send a signal to each matching process and print whether
+                       it was delivered, not permitted (EPERM) or the process
+                       was already gone (ESRCH)
+    wait               with --appear, wait for a matching process to start and
+                       print it like the main lookup. With --exit, wait for
+                       every matching process, or the pid <name>, to exit
+    watch              print a line each time a matching process starts or
+                       exits, or every process if there is no <name>
+
 Options:
-    name       The name of the executable running
-    --help -h  print this message
+    name               The name of the executable running, this is compared
+                       to the name reported by the OS and the full path
+                       to the executable. It can be left out when a filter
+                       like --user, --session, --port or --env is given
+    --contains         match any name containing <name>
+    --glob             treat <name> as a glob pattern
+    --regex            treat <name> as a regular expression
+    -i --ignore-case   ignore case when matching
+    --resolve          also match the file name of the executable and of the
+                       first argument, useful for names longer than 15 characters.
+                       The name that matched is printed after the pid
+    -f --full          match <name> against the whole command line instead
+    --arg=<arg>        only match processes with an argument like <arg>, in the
+                       form key[=value]. If key has no leading - then any number
+                       are allowed, so config=foo matches --config=foo
+                       and --config foo
+    --env=<env>        only match processes with an environment variable like
+                       <env>, in the form key, key=value or key~regex.
+                       <name> can be left out
+    --with-env         include each process' environment in the json formats
+    --user=<users>     only match processes whose real user is in the comma
+                       separated list, by name or number
+    --euid=<users>     only match processes whose effective user is in the
+                       comma separated list, by name or number
+    --group=<groups>   only match processes whose real group is in the comma
+                       separated list, by name or number
+    --parent=<pids>    only match processes whose parent is in the comma
+                       separated list of pids
+    --pgroup=<pgids>   only match processes in one of the comma separated
+                       process groups
+    --session=<sids>   only match processes in one of the comma separated
+                       sessions
+    --terminal=<ttys>  only match processes controlled by one of the comma
+                       separated terminals, like pts/0 or tty1
+    --port=<port>      only match processes listening on a port, like 8080,
+                       8080/tcp or 53/udp. <name> can be left out to match
+                       any process, the json formats include the bound address
+    --holding=<path>   only match processes with <path> open as a file, working
+                       directory, root or memory map. <name> can be left out
+    --lock=<path>      only match processes holding or waiting for a lock on
+                       <path>, the json formats include the lock's type and
+                       mode. <name> can be left out
+    --mount            with --holding or --lock, match anything on the same
+                       file system as <path>, for finding what keeps a mount
+                       busy
+    --unix-socket=<path>  only match processes listening on the unix socket
+                       <path>, or connected to it. Abstract sockets start
+                       with @. <name> can be left out
+    --all              print every matching pid, one per line
+    --newest           if more than one process matches, pick the newest
+    --oldest           if more than one process matches, pick the oldest
+    --first            if more than one process matches, pick the lowest pid
+    --format=<format>  how to print the matching processes, one of pid, json,
+                       ndjson, csv or tsv, defaults to pid. For tree and
+                       ancestors one of ascii, json or dot, defaults to ascii.
+                       For watch one of line or ndjson, defaults to line
+    --output=<tmpl>    print each matching process using a template like
+                       '{pid}\t{exe}\t{args}', any field included in
+                       the json format can be used
+    --proc-root=<dir>  read processes from a directory laid out like /proc
+                       instead of the running system, for example the
+                       host's /proc mounted into a container, not
+                       allowed with kill or wait --exit
+    --etc-root=<dir>   read user and group names from the passwd and group
+                       files in a directory laid out like /etc, usually
+                       the one belonging to --proc-root
+    -s --signal=<sig>  the signal kill sends, by name or number [default: TERM]
+    --escalate=<plan>  with kill, send each signal in a comma separated list
+                       like TERM:10s,INT:5s,KILL in turn, waiting up to the
+                       given time (default 5s) for the processes to exit
+                       before the next one, and print which signal stopped
+                       each process
+    -n --dry-run       print what kill would do without sending anything
+    -y --yes           don't ask before kill signals more than one process
+    --appear           with wait, wait for a process to start
+    --exit             with wait, wait for processes to exit
+    --timeout=<time>   with wait, give up after a time like 30s, 500ms or 2m
+    --interval=<time>  with watch, how often to look for changes [default: 1s]
+    --netlink          with watch, also look as soon as the kernel reports a
+                       process starting or exiting, this needs root
+    -q --quiet         don't print anything, only set the exit status
+    --help -h          print this message
+
+Exit status:
+    0  a process was found
+    1  no process matched
+    2  the arguments were invalid
+    3  something went wrong reading or signalling the processes
+    4  more than one process matched
+    5  wait gave up before the processes started or exited
+    6  kill was declined when asked to confirm
 ";
+
+const FOUND: i32 = 0;
+const NO_MATCH: i32 = 1;
+const USAGE: i32 = 2;
+const FATAL: i32 = 3;
+const AMBIGUOUS: i32 = 4;
+const TIMEOUT: i32 = 5;
+const DECLINED: i32 = 6;
+
+/// How often wait looks for new processes
+const SCAN_INTERVAL: Duration = Duration::from_millis(100);
+/// The least time between two watch scans started by process events,
+/// so a busy system can't keep it rescanning
+#[cfg(target_os = "linux")]
+const MIN_SCAN_GAP: Duration = Duration::from_millis(250);
+
 #[derive(Deserialize)]
 struct Args {
+    cmd_tree: bool,
+    cmd_ancestors: bool,
+    cmd_kill: bool,
+    cmd_wait: bool,
+    cmd_watch: bool,
     arg_name: String,
+    flag_contains: bool,
+    flag_glob: bool,
+    flag_regex: bool,
+    flag_ignore_case: bool,
+    flag_resolve: bool,
+    flag_full: bool,
+    flag_arg: Vec<String>,
+    flag_env: Vec<String>,
+    flag_with_env: bool,
+    flag_all: bool,
+    flag_newest: bool,
+    flag_oldest: bool,
+    flag_first: bool,
+    flag_format: Option<String>,
+    flag_output: Option<String>,
+    flag_proc_root: Option<String>,
+    flag_etc_root: Option<String>,
+    flag_quiet: bool,
+    flag_signal: String,
+    flag_dry_run: bool,
+    flag_escalate: Option<String>,
+    flag_exit: bool,
+    flag_timeout: Option<String>,
+    flag_interval: String,
+    flag_netlink: bool,
+    flag_yes: bool,
+    flag_user: Option<String>,
+    flag_euid: Option<String>,
+    flag_group: Option<String>,
+    flag_parent: Option<String>,
+    flag_pgroup: Option<String>,
+    flag_session: Option<String>,
+    flag_terminal: Option<String>,
+    flag_port: Option<String>,
+    flag_holding: Option<String>,
+    flag_mount: bool,
+    flag_lock: Option<String>,
+    flag_unix_socket: Option<String>,
+}
+
+impl Args {
+    fn mode(&self) -> Mode {
+        // watching without a name matches everything
+        if self.flag_contains || self.arg_name.is_empty() {
+            Mode::Contains
+        } else if self.flag_glob {
+            Mode::Glob
+        } else if self.flag_regex {
+            Mode::Regex
+        } else {
+            Mode::Exact
+        }
+    }
+
+    /// Whether a filter like an owner, port or environment variable
+    /// was given, in which case a name isn't needed
+    fn has_filter(&self) -> bool {
+        self.flag_user.is_some()
+            || self.flag_euid.is_some()
+            || self.flag_group.is_some()
+            || self.flag_parent.is_some()
+            || self.flag_pgroup.is_some()
+            || self.flag_session.is_some()
+            || self.flag_terminal.is_some()
+            || self.flag_port.is_some()
+            || self.flag_holding.is_some()
+            || self.flag_unix_socket.is_some()
+            || self.flag_lock.is_some()
+            || !self.flag_env.is_empty()
+    }
+
+    /// What is being looked for, for error messages
+    fn target(&self) -> String {
+        let mut parts = vec![];
+        if !self.arg_name.is_empty() {
+            parts.push(self.arg_name.clone());
+        }
+        if let Some(ref users) = self.flag_user {
+            parts.push(format!("user {}", users));
+        }
+        if let Some(ref users) = self.flag_euid {
+            parts.push(format!("effective user {}", users));
+        }
+        if let Some(ref groups) = self.flag_group {
+            parts.push(format!("group {}", groups));
+        }
+        if let Some(ref pids) = self.flag_parent {
+            parts.push(format!("parent {}", pids));
+        }
+        if let Some(ref pgids) = self.flag_pgroup {
+            parts.push(format!("process group {}", pgids));
+        }
+        if let Some(ref sids) = self.flag_session {
+            parts.push(format!("session {}", sids));
+        }
+        if let Some(ref ttys) = self.flag_terminal {
+            parts.push(format!("terminal {}", ttys));
+        }
+        if let Some(ref port) = self.flag_port {
+            parts.push(format!("port {}", port));
+        }
+        if let Some(ref path) = self.flag_holding {
+            parts.push(format!("holding {}", path));
+        }
+        if let Some(ref path) = self.flag_unix_socket {
+            parts.push(format!("unix socket {}", path));
+        }
+        if let Some(ref path) = self.flag_lock {
+            parts.push(format!("lock on {}", path));
+        }
+        for env in &self.flag_env {
+            parts.push(format!("env {}", env));
+        }
+        parts.join(", ")
+    }
+
+    fn selection(&self) -> Selection {
+        if self.flag_all || (self.flag_exit && !self.flag_newest && !self.flag_oldest && !self.flag_first) {
+            Selection::All
+        } else if self.flag_newest {
+            Selection::Newest
+        } else if self.flag_oldest {
+            Selection::Oldest
+        } else if self.flag_first {
+            Selection::First
+        } else {
+            Selection::Only
+        }
+    }
 }
 
-fn main() -> Result<(), Error> {
+fn main() {
     let args: Args = Docopt::new(HELP)
                 .and_then(|d| d.deserialize())
-                .unwrap_or_else(|e| e.exit());
-    if args.arg_name == String::new() {
+                .unwrap_or_else(|e| {
+                    if e.fatal() {
+                        eprintln!("{}", e);
+                        ::std::process::exit(USAGE);
+                    }
+                    println!("{}", e);
+                    ::std::process::exit(FOUND);
+                });
+    let command = args.cmd_tree || args.cmd_ancestors || args.cmd_kill || args.cmd_wait || args.cmd_watch;
+    if args.arg_name == String::new() && !command && !args.has_filter() {
         println!("{}", HELP);
-        ::std::process::exit(0);
-    }
-    let processes = get_processes()?;
-    let matches: Vec<Process> = processes.into_iter().filter(|p| p.cmd == args.arg_name).collect();
-    if matches.len() > 1 {
-        Err(Error::Other(format!("more than one process with the name {}", args.arg_name)))
-    } else if matches.len() < 1 {
-        Err(Error::Other(format!("no process found for {}", args.arg_name)))
-    } else {
-        println!("{}", matches[0].pid);
-        Ok(())
+        ::std::process::exit(FOUND);
     }
-}
-#[cfg(not(target_os = "macos"))]
-fn get_processes() -> Result<Vec<Process>, Error> {
-    let ret = WalkDir::new("/proc").min_depth(1).max_depth(1).follow_links(true).into_iter().filter_map(|res| {
-        if let Ok(entry) = res {
-            if entry.file_type().is_dir() {
-                if let Ok(pid) = entry.file_name().to_string_lossy().parse::<usize>() {
-                    get_info_for(pid)
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
+    if let Err(e) = run(&args) {
+        if !args.flag_quiet {
+            eprintln!("{}", e);
         }
-    }).collect();
-    Ok(ret)
+        ::std::process::exit(exit_code(&e));
+    }
 }
-#[cfg(not(target_os = "macos"))]
-fn get_info_for(pid: usize) -> Option<Process> {
-    let base = format!("/proc/{}", pid);
-    let comm = get_str_for(&format!("{}/comm", base))?;
-    let cmd_line = get_cmd_line(&format!("{}/cmdline", base))?;
-    let exe = get_link_for(&format!("{}/exe", base))?;
-    Some(Process {
-        pid,
-        cmd: comm,
-        args: cmd_line,
-        full_cmd_path: exe,
-    })
+
+fn exit_code(e: &Error) -> i32 {
+    match e {
+        Error::NotFound(_) => NO_MATCH,
+        Error::Invalid(_) | Error::ParseInt(_) | Error::Regex(_) => USAGE,
+        Error::Ambiguous(_) => AMBIGUOUS,
+        Error::Timeout(_) => TIMEOUT,
+        Error::Declined(_) => DECLINED,
+        _ => FATAL,
+    }
 }
-#[cfg(not(target_os = "macos"))]
-fn get_cmd_line(path: &str) -> Option<Vec<String>> {
-    let cmd_line = get_str_for(path)?;
-    let mut all = cmd_line.split('\u{0}');
-    let _comm = all.next();
-    Some(all.map(String::from).collect())
+
+fn run(args: &Args) -> Result<(), Error> {
+    if args.arg_name.is_empty() && !args.cmd_watch && !args.has_filter() {
+        return Err(Error::Invalid("a <name> or a filter like --user or --port is needed".to_string()));
+    }
+    // signals and exit checks always go to processes on this system, so
+    // they can't act on what was read from another root
+    if args.flag_proc_root.is_some() && (args.cmd_kill || (args.cmd_wait && args.flag_exit)) {
+        return Err(Error::Invalid("--proc-root can't be used with kill or wait --exit".to_string()));
+    }
+    if args.cmd_tree || args.cmd_ancestors {
+        return run_tree(args);
+    }
+    if args.cmd_kill {
+        return run_kill(args);
+    }
+    if args.cmd_watch {
+        return run_watch(args);
+    }
+    if args.cmd_wait && args.flag_exit {
+        return wait_exit(args);
+    }
+    let format: Format = args.flag_format.as_deref().unwrap_or("pid").parse()?;
+    let template = match args.flag_output {
+        Some(ref output) => Some(Template::parse(output)?),
+        None => None,
+    };
+    let matcher = matcher(args)?;
+    let mut matches = if args.cmd_wait {
+        wait_appear(args, &matcher)?
+    } else {
+        find_matches(args, &matcher, &processes(args, &matcher)?)?
+    };
+    if args.flag_quiet {
+        return Ok(());
+    }
+    for m in &mut matches {
+        read_env(args, &mut m.process);
+    }
+    if let Some(template) = template {
+        for m in matches {
+            println!("{}", template.render(&m.process)?);
+        }
+        return Ok(());
+    }
+    if args.flag_resolve && format == Format::Pid {
+        for m in matches {
+            println!("{}\t{}", m.process.pid, m.source);
+        }
+        return Ok(());
+    }
+    let processes: Vec<_> = matches.into_iter().map(|m| m.process).collect();
+    let stdout = ::std::io::stdout();
+    write_processes(&mut stdout.lock(), &processes, format)
 }
-#[cfg(not(target_os = "macos"))]
-fn get_str_for(path: &str) -> Option<String> {
-    let ret = read_to_string(path).ok()?;
-    Some(ret.trim().to_string())
+
+/// Scan the processes until one matches
+fn wait_appear(args: &Args, matcher: &Matcher) -> Result<Vec<Match>, Error> {
+    let deadline = deadline(args)?;
+    loop {
+        match find_matches(args, matcher, &processes(args, matcher)?) {
+            Err(Error::NotFound(_)) => (),
+            found => return found,
+        }
+        let now = Instant::now();
+        let remaining = deadline.map(|d| d.saturating_duration_since(now));
+        if remaining == Some(Duration::from_secs(0)) {
+            return Err(Error::Timeout(args.target()));
+        }
+        sleep(remaining.map(|r| r.min(SCAN_INTERVAL)).unwrap_or(SCAN_INTERVAL));
+    }
 }
-#[cfg(not(target_os = "macos"))]
-fn get_link_for(path: &str) -> Option<String> {
-    let link = read_link(path).ok()?;
-    Some(link.to_string_lossy().to_string())
+
+/// Wait for every match, or the process with the pid given as
+/// the name, to exit. Nothing matching means there is nothing to wait for
+fn wait_exit(args: &Args) -> Result<(), Error> {
+    let deadline = deadline(args)?;
+    let matcher = matcher(args)?;
+    let processes = processes(args, &matcher)?;
+    let identities: Vec<_> = match args.arg_name.parse::<usize>() {
+        Ok(pid) => processes.iter().filter(|p| p.pid == pid).map(|p| p.identity()).collect(),
+        Err(_) => {
+            match find_matches(args, &matcher, &processes) {
+                Ok(matches) => matches.iter().map(|m| m.process.identity()).collect(),
+                Err(Error::NotFound(_)) => vec![],
+                Err(e) => return Err(e),
+            }
+        }
+    };
+    for identity in identities {
+        let handle = match Handle::open(identity)? {
+            Some(handle) => handle,
+            None => continue,
+        };
+        let timeout = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        if !handle.wait(timeout)? {
+            return Err(Error::Timeout(format!("{} to exit", args.target())));
+        }
+    }
+    Ok(())
 }
 
-#[cfg(target_os = "macos")]
-fn get_processes() -> Result<Vec<Process>, Error> {
-    let tups = mac::get_processes()?;
-    Ok(tups.into_iter().map(|(pid, cmd)| Process {
-        pid,
-        cmd,
-        full_cmd_path: String::new(),
-        args: vec![],
-    }).collect())
+fn deadline(args: &Args) -> Result<Option<Instant>, Error> {
+    match args.flag_timeout {
+        // a deadline too far away to represent is the same as none
+        Some(ref timeout) => Ok(Instant::now().checked_add(parse_duration(timeout)?)),
+        None => Ok(None),
+    }
 }
 
+fn run_watch(args: &Args) -> Result<(), Error> {
+    let ndjson = match args.flag_format.as_deref().unwrap_or("line") {
+        "line" => false,
+        "ndjson" => true,
+        other => return Err(Error::Invalid(format!("unknown watch format {}", other))),
+    };
+    let interval = parse_duration(&args.flag_interval)?;
+    let matcher = matcher(args)?;
+    let mut changes = Changes::new(args);
+    let own_pid = ::std::process::id() as usize;
+    let scan = |previous: &Snapshot| -> Result<Snapshot, Error> {
+        Ok(Snapshot::new(processes(args, &matcher)?.into_iter()
+            .filter(|p| p.pid != own_pid)
+            .filter_map(|p| matcher.check(p))
+            .map(|m| {
+                let mut process = hide_env(args, m.process);
+                // each process only has its environment read once
+                match previous.get(process.identity()) {
+                    Some(known) => {
+                        process.env = known.env.clone();
+                        process.hidden = known.hidden.clone();
+                    }
+                    None => read_env(args, &mut process),
+                }
+                process
+            })
+            .collect()))
+    };
+    let mut snapshot = scan(&Snapshot::default())?;
+    let stdout = ::std::io::stdout();
+    loop {
+        changes.wait(interval)?;
+        let next = scan(&snapshot)?;
+        if !args.flag_quiet {
+            let mut out = stdout.lock();
+            for event in snapshot.diff(&next, SystemTime::now()) {
+                if ndjson {
+                    serde_json::to_writer(&mut out, &event)?;
+                    writeln!(out)?;
+                } else {
+                    writeln!(out, "{}", event)?;
+                }
+            }
+            out.flush()?;
+        }
+        snapshot = next;
+    }
+}
 
-#[derive(Debug)]
-struct Process {
-    pub pid: usize,
-    pub cmd: String,
-    pub full_cmd_path: String,
-    pub args: Vec<String>,
+/// Decides when watch should look at the processes again
+struct Changes {
+    #[cfg(target_os = "linux")]
+    connector: Option<ProcConnector>,
+    #[cfg(target_os = "linux")]
+    last: Instant,
 }
 
+impl Changes {
+    #[cfg(target_os = "linux")]
+    fn new(args: &Args) -> Changes {
+        let connector = if !args.flag_netlink {
+            None
+        } else {
+            match ProcConnector::open() {
+                Ok(connector) => Some(connector),
+                Err(e) => {
+                    if !args.flag_quiet {
+                        eprintln!("can't listen for process events ({}), checking every {}", e, args.flag_interval);
+                    }
+                    None
+                }
+            }
+        };
+        Changes { connector, last: Instant::now() }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn new(args: &Args) -> Changes {
+        if args.flag_netlink && !args.flag_quiet {
+            eprintln!("--netlink is only supported on linux, checking every {}", args.flag_interval);
+        }
+        Changes {}
+    }
 
-#[derive(Debug)]
-enum Error {
-    Doc(DocError),
-    Io(IoError),
-    Other(String),
-    ParseInt(::std::num::ParseIntError),
-    #[cfg(not(target_os = "macos"))]
-    Walk(WalkError),
+    /// Wait for a change or for `interval`, whichever is first, but
+    /// at least `MIN_SCAN_GAP` since the last time
+    fn wait(&mut self, interval: Duration) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(ref connector) = self.connector {
+                // events that arrive meanwhile are all handled by one scan
+                let since = self.last.elapsed();
+                if since < MIN_SCAN_GAP {
+                    sleep(MIN_SCAN_GAP - since);
+                }
+                connector.wait(interval.saturating_sub(self.last.elapsed()))?;
+                self.last = Instant::now();
+                return Ok(());
+            }
+        }
+        sleep(interval);
+        Ok(())
+    }
 }
 
-use std::error::Error as STDError;
-impl ::std::fmt::Display for Error {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        if let Some(e) = self.cause() {
-            return ::std::fmt::Display::fmt(e, f);
+fn run_tree(args: &Args) -> Result<(), Error> {
+    let format: TreeFormat = args.flag_format.as_deref().unwrap_or("ascii").parse()?;
+    let matcher = matcher(args)?;
+    let processes = processes(args, &matcher)?;
+    let matches = find_matches(args, &matcher, &processes)?;
+    if args.flag_quiet {
+        return Ok(());
+    }
+    let processes: Vec<_> = processes.into_iter().map(|p| hide_env(args, p)).collect();
+    let mut trees: Vec<_> = matches.iter().filter_map(|m| if args.cmd_tree {
+        Tree::descendants(&processes, m.process.pid)
+    } else {
+        Tree::ancestors(&processes, m.process.pid)
+    }).collect();
+    for tree in &mut trees {
+        read_tree_env(args, tree);
+    }
+    let stdout = ::std::io::stdout();
+    write_trees(&mut stdout.lock(), &trees, format)
+}
+
+fn run_kill(args: &Args) -> Result<(), Error> {
+    let signal: Signal = args.flag_signal.parse()?;
+    let escalation = match args.flag_escalate {
+        Some(ref steps) => Some(steps.parse::<Escalation>()?),
+        None => None,
+    };
+    let matcher = matcher(args)?;
+    let processes = processes(args, &matcher)?;
+    let matches = find_matches(args, &matcher, &processes)?;
+    let action = match args.flag_escalate {
+        Some(ref steps) => steps.clone(),
+        None => signal.to_string(),
+    };
+    if args.flag_dry_run {
+        if !args.flag_quiet {
+            for m in &matches {
+                println!("would send {} to {}", action, describe(&m.process));
+            }
         }
-        match self {
-            Error::Other(s) => s.fmt(f),
-            _ => unreachable!()
+        return Ok(());
+    }
+    if matches.len() > 1 && !args.flag_yes && !confirm(&matches, &action)? {
+        return Err(Error::Declined(format!("sending {} to {} processes", action, matches.len())));
+    }
+    if let Some(escalation) = escalation {
+        return escalate(args, &matches, &escalation);
+    }
+    let mut failed = 0;
+    for m in &matches {
+        let result = match send_signal(m.process.identity(), signal) {
+            Ok(Delivery::Sent) => Delivery::Sent.to_string(),
+            Ok(delivery) => {
+                failed += 1;
+                delivery.to_string()
+            }
+            Err(e) => {
+                failed += 1;
+                e.to_string()
+            }
+        };
+        if !args.flag_quiet {
+            println!("{}\t{}", m.process.pid, result);
         }
     }
+    if failed > 0 {
+        return Err(Error::Other(format!("failed to signal {} of {} processes", failed, matches.len())));
+    }
+    Ok(())
 }
 
-impl STDError for Error {
-    fn cause(&self) -> Option<&STDError> {
-        match self {
-            Error::Doc(ref e) => Some(e),
-            Error::Io(ref e) => Some(e),
-            Error::ParseInt(ref e) => Some(e),
-            #[cfg(not(target_os = "macos"))]
-            Error::Walk(ref e) => Some(e),
-            _ => None
+fn escalate(args: &Args, matches: &[Match], escalation: &Escalation) -> Result<(), Error> {
+    let identities: Vec<_> = matches.iter().map(|m| m.process.identity()).collect();
+    let results = escalation.run(&identities)?;
+    let mut failed = 0;
+    for (m, stopped) in matches.iter().zip(&results) {
+        match stopped {
+            Stopped::NotPermitted(_) | Stopped::Survived => failed += 1,
+            Stopped::By(_) | Stopped::AlreadyGone => (),
+        }
+        if !args.flag_quiet {
+            println!("{}\t{}", m.process.pid, stopped);
         }
     }
+    if failed > 0 {
+        return Err(Error::Other(format!("failed to stop {} of {} processes", failed, matches.len())));
+    }
+    Ok(())
 }
 
-impl From<IoError> for Error {
-    fn from(other: ::std::io::Error) -> Self {
-        Error::Io(other)
+/// Ask on the terminal whether every process in `matches` should be signalled
+fn confirm(matches: &[Match], action: &str) -> Result<bool, Error> {
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } == 0 {
+        return Err(Error::Invalid(format!("{} processes matched, pass --yes to signal all of them", matches.len())));
+    }
+    let stderr = ::std::io::stderr();
+    let mut err = stderr.lock();
+    for m in matches {
+        writeln!(err, "{}", describe(&m.process))?;
     }
+    write!(err, "send {} to these {} processes? [y/N] ", action, matches.len())?;
+    err.flush()?;
+    let mut answer = String::new();
+    ::std::io::stdin().lock().read_line(&mut answer)?;
+    let answer = answer.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
 }
 
-impl From<DocError> for Error {
-    fn from(other: DocError) -> Self {
-        Error::Doc(other)
+fn describe(p: &Process) -> String {
+    format!("{} {}", p.pid, p.display_command())
+}
+
+fn matcher(args: &Args) -> Result<Matcher, Error> {
+    let pattern = Pattern::new(&args.arg_name, args.mode(), args.flag_ignore_case)?;
+    let accounts = Accounts::load(args.flag_etc_root.as_deref().unwrap_or("/etc"));
+    let uids = parse_ids(&args.flag_user, |u| accounts.user_id(u))?;
+    let euids = parse_ids(&args.flag_euid, |u| accounts.user_id(u))?;
+    let gids = parse_ids(&args.flag_group, |g| accounts.group_id(g))?;
+    let parents = parse_ids(&args.flag_parent, |p| Ok(p.parse()?))?;
+    let pgroups = parse_ids(&args.flag_pgroup, |p| Ok(p.parse()?))?;
+    let sessions = parse_ids(&args.flag_session, |s| Ok(s.parse()?))?;
+    let terminals = parse_ids(&args.flag_terminal, |t| Ok(t.to_string()))?;
+    let mut matcher = Matcher::new(pattern)
+        .resolve(args.flag_resolve)
+        .full(args.flag_full)
+        .uids(uids)
+        .euids(euids)
+        .gids(gids)
+        .parents(parents)
+        .pgroups(pgroups)
+        .sessions(sessions)
+        .terminals(terminals);
+    if let Some(ref port) = args.flag_port {
+        matcher = matcher.port(port.parse()?);
+    }
+    if let Some(ref path) = args.flag_holding {
+        matcher = matcher.holding(Target::new(path, args.flag_mount)?);
+    }
+    if let Some(ref path) = args.flag_unix_socket {
+        matcher = matcher.unix_socket(UnixTarget::new(path));
     }
+    if let Some(ref path) = args.flag_lock {
+        matcher = matcher.lock(Target::new(path, args.flag_mount)?);
+    }
+    for env in &args.flag_env {
+        matcher = matcher.env(EnvPattern::parse(env)?);
+    }
+    Ok(args.flag_arg.iter().fold(matcher, |m, a| m.arg(ArgPattern::parse(a))))
+}
+
+/// Read every process once, so the tree views can see
+/// the processes around each match
+fn processes(args: &Args, matcher: &Matcher) -> Result<Vec<Process>, Error> {
+    let source: Box<dyn ProcessSource> = match (&args.flag_proc_root, &args.flag_etc_root) {
+        (None, None) => Box::new(native_source()),
+        (root, etc) => Box::new(ProcFs::new(root.as_deref().unwrap_or("/proc"))
+            .etc(etc.as_deref().unwrap_or("/etc"))),
+    };
+    processes_for(&source, matcher)
 }
-#[cfg(not(target_os = "macos"))]
-impl From<WalkError> for Error {
-    fn from(other: WalkError) -> Self {
-        Error::Walk(other)
+
+/// Check `processes` against `matcher`, leaving out this process, and
+/// apply the selection flags
+fn find_matches(args: &Args, matcher: &Matcher, processes: &[Process]) -> Result<Vec<Match>, Error> {
+    let own_pid = ::std::process::id() as usize;
+    let found: Vec<_> = processes.iter()
+        .filter(|p| p.pid != own_pid)
+        .filter_map(|p| matcher.check(p.clone()))
+        .map(|mut m| {
+            m.process = hide_env(args, m.process);
+            m
+        })
+        .collect();
+    let matches = select(found, args.selection())?;
+    if matches.is_empty() {
+        return Err(Error::NotFound(args.target()));
+    }
+    Ok(matches)
+}
+
+/// Read the environment of a process about to be shown with --with-env,
+/// unless it was already read to check --env. Only these processes
+/// have theirs read, since it may hold secrets
+fn read_env(args: &Args, process: &mut Process) {
+    if args.flag_with_env && args.flag_env.is_empty() {
+        let root = Path::new(args.flag_proc_root.as_deref().unwrap_or("/proc"));
+        attach_environ(::std::slice::from_mut(process), root);
     }
 }
 
-impl From<::std::num::ParseIntError> for Error {
-    fn from(other: ::std::num::ParseIntError) -> Self {
-        Error::ParseInt(other)
+fn read_tree_env(args: &Args, tree: &mut Tree) {
+    read_env(args, &mut tree.process);
+    for child in &mut tree.children {
+        read_tree_env(args, child);
     }
-}
\ No newline at end of file
+}
+
+/// The environment is read to check --env but only shown with --with-env
+fn hide_env(args: &Args, mut process: Process) -> Process {
+    if !args.flag_with_env {
+        process.env = None;
+    }
+    process
+}
+
+/// Parse a comma separated list of ids or names
+fn parse_ids<T, F>(list: &Option<String>, lookup: F) -> Result<Vec<T>, Error>
+where F: Fn(&str) -> Result<T, Error> {
+    match list {
+        Some(list) => list.split(',').filter(|s| !s.is_empty()).map(lookup).collect(),
+        None => Ok(vec![]),
+    }
+}
@@ -0,0 +1,481 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::Path;
+
+use regex::{escape, Regex, RegexBuilder};
+
+use super::{attach_environ, attach_holding, attach_listening, attach_locks, attach_unix_sockets, Error, Port, Process, Target, UnixTarget};
+
+/// How the text of a `Pattern` should be interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// The whole name must be equal to the text
+    Exact,
+    /// The name must contain the text
+    Contains,
+    /// The whole name must match the text as a glob, supporting
+    /// `*`, `?` and `[...]`
+    Glob,
+    /// The name must match the text as a regular expression
+    Regex,
+}
+
+/// Something to compare a process' names against
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+}
+
+impl Pattern {
+    pub fn new(text: &str, mode: Mode, ignore_case: bool) -> Result<Pattern, Error> {
+        let source = match mode {
+            Mode::Exact => format!("^{}$", escape(text)),
+            Mode::Contains => escape(text),
+            Mode::Glob => format!("^{}$", glob_to_regex(text)),
+            Mode::Regex => text.to_string(),
+        };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(ignore_case)
+            .build()?;
+        Ok(Pattern { regex })
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut ret = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => ret.push_str(".*"),
+            '?' => ret.push('.'),
+            '[' => {
+                let mut rest = chars.clone();
+                let mut class = String::new();
+                if let Some(&'!') = rest.peek() {
+                    class.push('^');
+                    rest.next();
+                }
+                // a ] straight after the opening bracket is part of the class
+                let mut first = true;
+                let mut closed = false;
+                for c in rest.by_ref() {
+                    if c == ']' && !first {
+                        closed = true;
+                        break;
+                    }
+                    first = false;
+                    if c == '\\' || c == '[' || c == ']' || c == '&' || c == '~' {
+                        class.push('\\');
+                    }
+                    class.push(c);
+                }
+                if closed {
+                    ret.push('[');
+                    ret.push_str(&class);
+                    ret.push(']');
+                    chars = rest;
+                } else {
+                    // an unterminated class is just a literal [
+                    ret.push_str("\\[");
+                }
+            }
+            _ => ret.push_str(&escape(&c.to_string())),
+        }
+    }
+    ret
+}
+
+/// Which of a process' names produced a match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameSource {
+    /// The name reported by the OS, which may be truncated
+    Comm,
+    /// The full path to the executable
+    Path,
+    /// The file name of the executable
+    Exe,
+    /// The file name of the first command line argument
+    Arg0,
+    /// The whole command line, joined with spaces
+    CmdLine,
+}
+
+impl Display for NameSource {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            NameSource::Comm => "comm".fmt(f),
+            NameSource::Path => "path".fmt(f),
+            NameSource::Exe => "exe".fmt(f),
+            NameSource::Arg0 => "arg0".fmt(f),
+            NameSource::CmdLine => "cmdline".fmt(f),
+        }
+    }
+}
+
+/// Matches a single command line argument and its value, for example
+/// `--config=/etc/foo.toml`, `--config /etc/foo.toml` or `-Dservice.name=api`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgPattern {
+    key: String,
+    value: Option<String>,
+}
+
+impl ArgPattern {
+    /// Parse a pattern in the form `key[=value]`
+    ///
+    /// If `key` doesn't start with a `-` then any number
+    /// of leading `-`s on the argument are ignored
+    pub fn parse(text: &str) -> ArgPattern {
+        let mut parts = text.splitn(2, '=');
+        let key = parts.next().unwrap_or_default().to_string();
+        let value = parts.next().map(String::from);
+        ArgPattern { key, value }
+    }
+
+    pub fn is_match(&self, args: &[String]) -> bool {
+        args.iter().enumerate().any(|(i, arg)| {
+            let mut parts = arg.splitn(2, '=');
+            let key = parts.next().unwrap_or_default();
+            if !self.key_matches(key) {
+                return false;
+            }
+            match (&self.value, parts.next()) {
+                (None, _) => true,
+                (Some(expected), Some(value)) => expected == value,
+                (Some(expected), None) => args.get(i + 1) == Some(expected),
+            }
+        })
+    }
+
+    fn key_matches(&self, key: &str) -> bool {
+        if self.key.starts_with('-') {
+            self.key == key
+        } else {
+            key.starts_with('-') && self.key == key.trim_start_matches('-')
+        }
+    }
+}
+
+/// Matches a single environment variable, for example `SERVICE_ID`,
+/// `SERVICE_ID=api-1` or `SERVICE_ID~^api-`
+#[derive(Debug, Clone)]
+pub struct EnvPattern {
+    key: String,
+    value: EnvValue,
+}
+
+#[derive(Debug, Clone)]
+enum EnvValue {
+    Any,
+    Exact(String),
+    Regex(Regex),
+}
+
+impl EnvPattern {
+    /// Parse a pattern in the form `key`, `key=value` or `key~regex`
+    pub fn parse(text: &str) -> Result<EnvPattern, Error> {
+        let (key, value) = match text.find(&['=', '~'][..]) {
+            Some(i) if text[i..].starts_with('=') => (&text[..i], EnvValue::Exact(text[i + 1..].to_string())),
+            Some(i) => (&text[..i], EnvValue::Regex(Regex::new(&text[i + 1..])?)),
+            None => (text, EnvValue::Any),
+        };
+        Ok(EnvPattern { key: key.to_string(), value })
+    }
+
+    pub fn is_match(&self, env: &BTreeMap<String, String>) -> bool {
+        match (env.get(&self.key), &self.value) {
+            (None, _) => false,
+            (Some(_), EnvValue::Any) => true,
+            (Some(value), EnvValue::Exact(expected)) => value == expected,
+            (Some(value), EnvValue::Regex(regex)) => regex.is_match(value),
+        }
+    }
+}
+
+/// Decides which processes match a lookup
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    pattern: Pattern,
+    resolve: bool,
+    full: bool,
+    args: Vec<ArgPattern>,
+    uids: Vec<u32>,
+    euids: Vec<u32>,
+    gids: Vec<u32>,
+    parents: Vec<usize>,
+    pgroups: Vec<usize>,
+    sessions: Vec<usize>,
+    terminals: Vec<String>,
+    port: Option<Port>,
+    holding: Option<Target>,
+    unix_socket: Option<UnixTarget>,
+    lock: Option<Target>,
+    env: Vec<EnvPattern>,
+}
+
+impl Matcher {
+    /// Match a process' name or the full path to its executable
+    /// against `pattern`
+    pub fn new(pattern: Pattern) -> Matcher {
+        Matcher {
+            pattern,
+            resolve: false,
+            full: false,
+            args: vec![],
+            uids: vec![],
+            euids: vec![],
+            gids: vec![],
+            parents: vec![],
+            pgroups: vec![],
+            sessions: vec![],
+            terminals: vec![],
+            port: None,
+            holding: None,
+            unix_socket: None,
+            lock: None,
+            env: vec![],
+        }
+    }
+
+    /// Also check the file names of the executable and of `arg0`
+    pub fn resolve(mut self, resolve: bool) -> Matcher {
+        self.resolve = resolve;
+        self
+    }
+
+    /// Check the whole command line instead of the process' names
+    pub fn full(mut self, full: bool) -> Matcher {
+        self.full = full;
+        self
+    }
+
+    /// Require an argument matching `arg` in addition to the name
+    pub fn arg(mut self, arg: ArgPattern) -> Matcher {
+        self.args.push(arg);
+        self
+    }
+
+    /// Require an environment variable matching `env`, which needs
+    /// the environments read first, see `attach`
+    pub fn env(mut self, env: EnvPattern) -> Matcher {
+        self.env.push(env);
+        self
+    }
+
+    /// Only match processes whose real user id is one of `uids`
+    pub fn uids(mut self, uids: Vec<u32>) -> Matcher {
+        self.uids = uids;
+        self
+    }
+
+    /// Only match processes whose effective user id is one of `euids`
+    pub fn euids(mut self, euids: Vec<u32>) -> Matcher {
+        self.euids = euids;
+        self
+    }
+
+    /// Only match processes whose real group id is one of `gids`
+    pub fn gids(mut self, gids: Vec<u32>) -> Matcher {
+        self.gids = gids;
+        self
+    }
+
+    /// Only match processes whose parent is one of `ppids`
+    pub fn parents(mut self, ppids: Vec<usize>) -> Matcher {
+        self.parents = ppids;
+        self
+    }
+
+    /// Only match processes in one of the process groups `pgids`
+    pub fn pgroups(mut self, pgids: Vec<usize>) -> Matcher {
+        self.pgroups = pgids;
+        self
+    }
+
+    /// Only match processes in one of the sessions `sids`
+    pub fn sessions(mut self, sids: Vec<usize>) -> Matcher {
+        self.sessions = sids;
+        self
+    }
+
+    /// Only match processes controlled by one of `ttys`, which
+    /// can be given with or without a leading `/dev/`
+    pub fn terminals(mut self, ttys: Vec<String>) -> Matcher {
+        self.terminals = ttys.into_iter()
+            .map(|t| t.trim_start_matches("/dev/").to_string())
+            .collect();
+        self
+    }
+
+    /// Only match processes listening on `port`, which needs the
+    /// sockets read first, see `attach`
+    pub fn port(mut self, port: Port) -> Matcher {
+        self.port = Some(port);
+        self
+    }
+
+    /// Only match processes holding `target` open, which needs the
+    /// open files read first, see `attach`
+    pub fn holding(mut self, target: Target) -> Matcher {
+        self.holding = Some(target);
+        self
+    }
+
+    /// Only match processes listening on or connected to the unix socket
+    /// `target`, which needs the sockets read first, see `attach`
+    pub fn unix_socket(mut self, target: UnixTarget) -> Matcher {
+        self.unix_socket = Some(target);
+        self
+    }
+
+    /// Only match processes holding or waiting for a lock on `target`,
+    /// which needs the locks read first, see `attach`
+    pub fn lock(mut self, target: Target) -> Matcher {
+        self.lock = Some(target);
+        self
+    }
+
+    /// Read what the port, holding, unix socket, lock and env checks
+    /// look at from `proc_root` onto `processes`
+    ///
+    /// `find_in` does this for sources with a proc root, processes
+    /// from other sources need these fields filled in some other way
+    pub fn attach(&self, processes: &mut [Process], proc_root: &Path) -> Result<(), Error> {
+        if let Some(ref port) = self.port {
+            attach_listening(processes, proc_root, port)?;
+        }
+        if let Some(ref target) = self.holding {
+            attach_holding(processes, proc_root, target)?;
+        }
+        if let Some(ref target) = self.unix_socket {
+            attach_unix_sockets(processes, proc_root, target)?;
+        }
+        if let Some(ref target) = self.lock {
+            attach_locks(processes, proc_root, target)?;
+        }
+        if !self.env.is_empty() {
+            attach_environ(processes, proc_root);
+        }
+        Ok(())
+    }
+
+    /// Check `process`, returning it as a `Match` if any of its names match
+    pub fn check(&self, process: Process) -> Option<Match> {
+        if !id_matches(&self.uids, &process.uid)
+            || !id_matches(&self.euids, &process.euid)
+            || !id_matches(&self.gids, &process.gid)
+            || !id_matches(&self.parents, &process.ppid)
+            || !id_matches(&self.pgroups, &process.pgid)
+            || !id_matches(&self.sessions, &process.sid)
+            || !id_matches(&self.terminals, &process.tty) {
+            return None;
+        }
+        if let Some(port) = self.port {
+            let listening = process.listening.as_ref()?;
+            if !listening.iter().any(|s| port.matches(s)) {
+                return None;
+            }
+        }
+        if let Some(target) = self.holding {
+            let holding = process.holding.as_ref()?;
+            if !holding.iter().any(|h| target.matches(h)) {
+                return None;
+            }
+        }
+        if let Some(ref target) = self.unix_socket {
+            let sockets = process.unix_sockets.as_ref()?;
+            if !sockets.iter().any(|s| target.matches(s)) {
+                return None;
+            }
+        }
+        if let Some(target) = self.lock {
+            let locks = process.locks.as_ref()?;
+            if !locks.iter().any(|l| target.matches_fs_file(l.device, l.inode)) {
+                return None;
+            }
+        }
+        if !self.env.is_empty() {
+            let env = process.env.as_ref()?;
+            if !self.env.iter().all(|e| e.is_match(env)) {
+                return None;
+            }
+        }
+        if !self.args.is_empty() {
+            let args = process.args.as_ref()?;
+            if !self.args.iter().all(|a| a.is_match(args)) {
+                return None;
+            }
+        }
+        let source = if self.full {
+            let cmd_line = process.command_line()?;
+            if !self.pattern.is_match(&cmd_line) {
+                return None;
+            }
+            NameSource::CmdLine
+        } else {
+            process.names().into_iter()
+                .filter(|&(source, _)| self.resolve || source == NameSource::Comm || source == NameSource::Path)
+                .find(|&(_, name)| self.pattern.is_match(name))?.0
+        };
+        Some(Match { process, source })
+    }
+}
+
+/// An empty filter allows anything, otherwise the id must be known
+/// and in the list
+fn id_matches<T: PartialEq>(filter: &[T], id: &Option<T>) -> bool {
+    filter.is_empty() || id.as_ref().map(|id| filter.contains(id)).unwrap_or(false)
+}
+
+/// A process that matched a lookup
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub process: Process,
+    pub source: NameSource,
+}
+
+impl Process {
+    /// Every name this process might be known by, in the order
+    /// they should be checked
+    pub fn names(&self) -> Vec<(NameSource, &str)> {
+        let mut ret = vec![];
+        if let Some(ref cmd) = self.cmd {
+            ret.push((NameSource::Comm, cmd.as_str()));
+        }
+        if let Some(ref exe) = self.full_cmd_path {
+            // the kernel appends this when the executable has been replaced
+            let exe = exe.trim_end_matches(" (deleted)");
+            ret.push((NameSource::Path, exe));
+            if let Some(name) = file_name(exe) {
+                ret.push((NameSource::Exe, name));
+            }
+        }
+        if let Some(ref arg0) = self.arg0 {
+            if let Some(name) = file_name(arg0) {
+                ret.push((NameSource::Arg0, name));
+            }
+        }
+        ret
+    }
+
+    /// The command line this process was started with, joined with spaces
+    pub fn command_line(&self) -> Option<String> {
+        let mut parts: Vec<&str> = self.arg0.iter().map(|a| a.as_str()).collect();
+        if let Some(ref args) = self.args {
+            parts.extend(args.iter().map(|a| a.as_str()));
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" "))
+        }
+    }
+}
+
+fn file_name(path: &str) -> Option<&str> {
+    Path::new(path).file_name().and_then(|n| n.to_str())
+}
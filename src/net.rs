@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{Error, Field, Process};
+use procfs::get_fd_links;
+
+/// The tcp state meaning a socket is listening
+const TCP_LISTEN: &str = "0A";
+/// The udp state meaning a socket is bound but not connected
+const UDP_UNCONNECTED: &str = "07";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+/// A socket waiting for connections or datagrams
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Socket {
+    pub protocol: Protocol,
+    /// The address and port the socket is bound to
+    pub address: SocketAddr,
+    pub inode: u64,
+}
+
+/// A port to look for, and optionally which protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Port {
+    pub number: u16,
+    pub protocol: Option<Protocol>,
+}
+
+impl Port {
+    pub fn matches(&self, socket: &Socket) -> bool {
+        socket.address.port() == self.number
+            && self.protocol.map(|p| p == socket.protocol).unwrap_or(true)
+    }
+}
+
+impl FromStr for Port {
+    type Err = Error;
+    /// Parse a port like `8080`, `8080/tcp` or `53/udp`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let number = parts.next().unwrap_or_default().parse()
+            .map_err(|_| Error::Invalid(format!("invalid port {}", s)))?;
+        let protocol = match parts.next() {
+            None => None,
+            Some("tcp") => Some(Protocol::Tcp),
+            Some("udp") => Some(Protocol::Udp),
+            Some(other) => return Err(Error::Invalid(format!("unknown protocol {}", other))),
+        };
+        Ok(Port { number, protocol })
+    }
+}
+
+/// Read every listening tcp socket and bound udp socket out of
+/// `net/{tcp,tcp6,udp,udp6}` in a directory laid out like `/proc`
+///
+/// These only cover the network namespace of whoever reads them,
+/// which is normally the host's
+pub fn listening_sockets(proc_root: &Path) -> Result<Vec<Socket>, Error> {
+    let mut ret = vec![];
+    for &(file, protocol) in &[("tcp", Protocol::Tcp), ("tcp6", Protocol::Tcp), ("udp", Protocol::Udp), ("udp6", Protocol::Udp)] {
+        match read_to_string(proc_root.join("net").join(file)) {
+            Ok(text) => ret.extend(parse_sockets(&text, protocol)),
+            // ipv6 may be turned off
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(ret)
+}
+
+/// Parse the text of one of the `/proc/net` socket tables, keeping
+/// only the listening sockets. Lines that can't be read are skipped
+pub fn parse_sockets(text: &str, protocol: Protocol) -> Vec<Socket> {
+    let listening = match protocol {
+        Protocol::Tcp => TCP_LISTEN,
+        Protocol::Udp => UDP_UNCONNECTED,
+    };
+    text.lines().skip(1).filter_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.get(3) != Some(&listening) {
+            return None;
+        }
+        Some(Socket {
+            protocol,
+            address: parse_address(fields.get(1)?)?,
+            inode: fields.get(9)?.parse().ok()?,
+        })
+    }).collect()
+}
+
+/// Addresses are written as hex, with each 32 bit word of the
+/// address in host byte order, followed by the port in hex
+fn parse_address(text: &str) -> Option<SocketAddr> {
+    let mut parts = text.splitn(2, ':');
+    let ip = parts.next()?;
+    let port = u16::from_str_radix(parts.next()?, 16).ok()?;
+    let mut bytes = vec![];
+    for i in (0..ip.len()).step_by(8) {
+        let word = u32::from_str_radix(ip.get(i..i + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(&bytes);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// The inodes of every socket `pid` has open, read from the
+/// `socket:[inode]` links in its `fd` directory
+pub(crate) fn socket_inodes(proc_root: &Path, pid: usize) -> Result<Vec<u64>, Error> {
+    Ok(get_fd_links(proc_root, pid)?.into_iter()
+        .filter_map(|link| link.target.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok())
+        .collect())
+}
+
+/// Fill in `listening` on each of `processes` that holds one of the
+/// sockets bound to `port`
+pub fn attach_listening(processes: &mut [Process], proc_root: &Path, port: &Port) -> Result<(), Error> {
+    let sockets: HashMap<u64, Socket> = listening_sockets(proc_root)?.into_iter()
+        .filter(|s| port.matches(s))
+        .map(|s| (s.inode, s))
+        .collect();
+    if sockets.is_empty() {
+        return Ok(());
+    }
+    for p in processes {
+        match socket_inodes(proc_root, p.pid) {
+            Ok(inodes) => {
+                let held: Vec<_> = inodes.iter().filter_map(|i| sockets.get(i).cloned()).collect();
+                if !held.is_empty() {
+                    p.listening = Some(held);
+                }
+            }
+            Err(Error::Io(ref e)) if e.kind() == ErrorKind::PermissionDenied => p.hidden.push(Field::Listening),
+            Err(_) => (),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::io::Error as IoError;
+use std::mem;
+use std::time::Duration;
+
+use libc::{self, c_int, c_void};
+
+use super::Error;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_DUMP: u16 = 0x300;
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const UDIAG_SHOW_PEER: u32 = 0x4;
+const UNIX_DIAG_PEER: u16 = 2;
+/// nlmsghdr then unix_diag_req
+const UNIX_DIAG_REQ_LEN: usize = 16 + 24;
+/// The size of unix_diag_msg, its attributes follow
+const UNIX_DIAG_MSG_LEN: usize = 16;
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+/// nlmsghdr, cn_msg then the op
+const LISTEN_LEN: usize = 16 + 20 + 4;
+
+/// The kernel's proc connector, which sends a message every time
+/// a process forks, execs or exits
+///
+/// Subscribing needs `CAP_NET_ADMIN`, usually meaning root
+#[derive(Debug)]
+pub struct ProcConnector {
+    fd: c_int,
+}
+
+impl ProcConnector {
+    pub fn open() -> Result<ProcConnector, Error> {
+        let fd = unsafe {
+            libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_CONNECTOR)
+        };
+        if fd < 0 {
+            return Err(IoError::last_os_error().into());
+        }
+        let connector = ProcConnector { fd };
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as u16;
+        addr.nl_groups = CN_IDX_PROC;
+        let ret = unsafe {
+            libc::bind(
+                fd,
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(IoError::last_os_error().into());
+        }
+        let mut msg = Vec::with_capacity(LISTEN_LEN);
+        // nlmsghdr
+        msg.extend_from_slice(&(LISTEN_LEN as u32).to_ne_bytes());
+        msg.extend_from_slice(&NLMSG_DONE.to_ne_bytes());
+        msg.extend_from_slice(&0u16.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&::std::process::id().to_ne_bytes());
+        // cn_msg
+        msg.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        msg.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&4u16.to_ne_bytes());
+        msg.extend_from_slice(&0u16.to_ne_bytes());
+        msg.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+        let sent = unsafe { libc::send(fd, msg.as_ptr() as *const c_void, msg.len(), 0) };
+        if sent < 0 {
+            return Err(IoError::last_os_error().into());
+        }
+        Ok(connector)
+    }
+
+    /// Wait up to `timeout` for processes to change, returning whether
+    /// any did
+    ///
+    /// Every message already queued is read, so a burst of forks
+    /// only needs one rescan
+    pub fn wait(&self, timeout: Duration) -> Result<bool, Error> {
+        let mut pfd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ms = timeout.as_millis().min(c_int::MAX as u128) as c_int;
+        let ret = unsafe { libc::poll(&mut pfd, 1, ms) };
+        if ret < 0 {
+            let e = IoError::last_os_error();
+            if e.raw_os_error() == Some(libc::EINTR) {
+                return Ok(false);
+            }
+            return Err(e.into());
+        }
+        if ret == 0 {
+            return Ok(false);
+        }
+        let mut buf = [0u8; 4096];
+        loop {
+            let read = unsafe {
+                libc::recv(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len(), libc::MSG_DONTWAIT)
+            };
+            if read < 0 {
+                let e = IoError::last_os_error();
+                match e.raw_os_error() {
+                    Some(libc::EAGAIN) => return Ok(true),
+                    // the kernel dropped messages, which still means something changed
+                    Some(libc::ENOBUFS) | Some(libc::EINTR) => continue,
+                    _ => return Err(e.into()),
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ProcConnector {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+/// Ask the kernel for every unix socket that has a peer, mapping
+/// each socket's inode to its peer's inode
+pub(crate) fn unix_peers() -> Result<HashMap<u64, u64>, Error> {
+    let fd = unsafe {
+        libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_SOCK_DIAG)
+    };
+    if fd < 0 {
+        return Err(IoError::last_os_error().into());
+    }
+    let ret = read_unix_peers(fd);
+    unsafe { libc::close(fd) };
+    ret
+}
+
+fn read_unix_peers(fd: c_int) -> Result<HashMap<u64, u64>, Error> {
+    let mut msg = Vec::with_capacity(UNIX_DIAG_REQ_LEN);
+    // nlmsghdr
+    msg.extend_from_slice(&(UNIX_DIAG_REQ_LEN as u32).to_ne_bytes());
+    msg.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    msg.extend_from_slice(&(NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    // unix_diag_req, every state
+    msg.push(libc::AF_UNIX as u8);
+    msg.push(0);
+    msg.extend_from_slice(&0u16.to_ne_bytes());
+    msg.extend_from_slice(&u32::MAX.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    msg.extend_from_slice(&UDIAG_SHOW_PEER.to_ne_bytes());
+    msg.extend_from_slice(&[0; 8]);
+    let sent = unsafe { libc::send(fd, msg.as_ptr() as *const c_void, msg.len(), 0) };
+    if sent < 0 {
+        return Err(IoError::last_os_error().into());
+    }
+    let mut ret = HashMap::new();
+    let mut buf = vec![0u8; 32 * 1024];
+    loop {
+        let read = unsafe { libc::recv(fd, buf.as_mut_ptr() as *mut c_void, buf.len(), 0) };
+        if read < 0 {
+            let e = IoError::last_os_error();
+            if e.raw_os_error() == Some(libc::EINTR) {
+                continue;
+            }
+            return Err(e.into());
+        }
+        let mut data = &buf[..read as usize];
+        while data.len() >= 16 {
+            let len = u32_at(data, 0)? as usize;
+            let kind = u16_at(data, 4)?;
+            if len < 16 || len > data.len() {
+                return Err(truncated());
+            }
+            match kind {
+                NLMSG_DONE => return Ok(ret),
+                NLMSG_ERROR => {
+                    // an error of 0 is just an acknowledgement
+                    return match -(u32_at(data, 16)? as i32) {
+                        0 => Ok(ret),
+                        errno => Err(IoError::from_raw_os_error(errno).into()),
+                    };
+                }
+                _ => {
+                    if let Some((inode, peer)) = parse_unix_diag(&data[16..len]) {
+                        ret.insert(inode, peer);
+                    }
+                }
+            }
+            data = &data[align(len).min(data.len())..];
+        }
+    }
+}
+
+/// Pull the inode and peer inode out of a unix_diag_msg and its attributes
+fn parse_unix_diag(msg: &[u8]) -> Option<(u64, u64)> {
+    let inode = u32_at(msg, 4).ok()?;
+    let mut attrs = msg.get(UNIX_DIAG_MSG_LEN..)?;
+    while attrs.len() >= 4 {
+        let len = u16_at(attrs, 0).ok()? as usize;
+        let kind = u16_at(attrs, 2).ok()?;
+        if len < 4 || len > attrs.len() {
+            return None;
+        }
+        if kind == UNIX_DIAG_PEER {
+            return Some((u64::from(inode), u64::from(u32_at(attrs, 4).ok()?)));
+        }
+        attrs = &attrs[align(len).min(attrs.len())..];
+    }
+    None
+}
+
+/// Netlink messages and attributes are padded to 4 bytes
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn u16_at(data: &[u8], at: usize) -> Result<u16, Error> {
+    let mut bytes = [0; 2];
+    bytes.copy_from_slice(data.get(at..at + 2).ok_or_else(truncated)?);
+    Ok(u16::from_ne_bytes(bytes))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Result<u32, Error> {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(data.get(at..at + 4).ok_or_else(truncated)?);
+    Ok(u32::from_ne_bytes(bytes))
+}
+
+fn truncated() -> Error {
+    Error::Other("truncated netlink message".to_string())
+}
@@ -0,0 +1,106 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use serde_json::{self, Value};
+
+use super::{Error, Process};
+use template::shell_quote;
+
+/// How a list of processes should be written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Just the pid, one per line
+    Pid,
+    /// A single JSON array
+    Json,
+    /// One JSON object per line
+    NdJson,
+    /// Comma separated values with a header row
+    Csv,
+    /// Tab separated values with a header row
+    Tsv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pid" => Ok(Format::Pid),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::NdJson),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            _ => Err(Error::Invalid(format!("unknown format {}", s))),
+        }
+    }
+}
+
+/// Write `processes` to `out` in the provided format
+pub fn write_processes<W: Write>(out: &mut W, processes: &[Process], format: Format) -> Result<(), Error> {
+    match format {
+        Format::Pid => {
+            for p in processes {
+                writeln!(out, "{}", p.pid)?;
+            }
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, processes)?;
+            writeln!(out)?;
+        }
+        Format::NdJson => {
+            for p in processes {
+                serde_json::to_writer(&mut *out, p)?;
+                writeln!(out)?;
+            }
+        }
+        Format::Csv => write_table(out, processes, ",", csv_escape)?,
+        Format::Tsv => write_table(out, processes, "\t", tsv_escape)?,
+    }
+    Ok(())
+}
+
+/// Write one row per process with a column for each of its fields,
+/// the columns are taken from how serde sees a `Process` so new fields
+/// show up here automatically
+fn write_table<W: Write>(out: &mut W, processes: &[Process], sep: &str, escape: fn(&str) -> String) -> Result<(), Error> {
+    let columns: Vec<String> = match serde_json::to_value(Process::default())? {
+        Value::Object(map) => map.keys().cloned().collect(),
+        _ => vec![],
+    };
+    writeln!(out, "{}", columns.iter().map(|c| escape(c)).collect::<Vec<_>>().join(sep))?;
+    for p in processes {
+        let value = serde_json::to_value(p)?;
+        let row: Vec<String> = columns.iter()
+            .map(|c| escape(&cell(&value[c.as_str()])))
+            .collect();
+        writeln!(out, "{}", row.join(sep))?;
+    }
+    Ok(())
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(a) => a.iter().map(|v| match v {
+            Value::String(s) => shell_quote(s),
+            other => cell(other),
+        }).collect::<Vec<_>>().join(" "),
+        other => other.to_string(),
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn tsv_escape(value: &str) -> String {
+    value.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
@@ -0,0 +1,205 @@
+use super::{Accounts, Error, Field, Process, Stat};
+
+use std::collections::BTreeMap;
+use std::fs::{read, read_dir, read_link};
+use std::io::{Error as IoError, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use libc::{sysconf, _SC_CLK_TCK};
+use walkdir::WalkDir;
+
+/// Read every process out of a directory laid out like `/proc`
+///
+/// If `plain_links` is true then files that would be symlinks in
+/// `/proc` are allowed to be regular files holding the link's target
+pub(crate) fn get_processes(root: &Path, plain_links: bool, accounts: &Accounts) -> Result<Vec<Process>, Error> {
+    if !root.is_dir() {
+        return Err(Error::Other(format!("{} is not a directory", root.display())));
+    }
+    let boot_time = get_boot_time(root);
+    let ret = WalkDir::new(root).min_depth(1).max_depth(1).follow_links(true).into_iter().filter_map(|res| {
+        if let Ok(entry) = res {
+            if entry.file_type().is_dir() {
+                if let Ok(pid) = entry.file_name().to_string_lossy().parse::<usize>() {
+                    get_info_for(root, pid, boot_time, plain_links, accounts)
+                } else {
+                    None
+                }
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }).collect();
+    Ok(ret)
+}
+
+fn get_info_for(root: &Path, pid: usize, boot_time: Option<u64>, plain_links: bool, accounts: &Accounts) -> Option<Process> {
+    let base = root.join(pid.to_string());
+    let base = base.to_string_lossy();
+    let mut ret = Process {
+        pid,
+        ..Process::default()
+    };
+    match get_str_for(&format!("{}/comm", base)) {
+        // the process exited while we were looking at it
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return None,
+        res => ret.cmd = check(res, Field::Cmd, &mut ret.hidden),
+    }
+    if let Some((arg0, args)) = check(get_cmd_line(&format!("{}/cmdline", base)), Field::Args, &mut ret.hidden) {
+        ret.arg0 = arg0;
+        ret.args = Some(args);
+    }
+    ret.full_cmd_path = check(get_link_for(&format!("{}/exe", base), plain_links), Field::FullCmdPath, &mut ret.hidden);
+    if let Ok(stat) = Stat::read(format!("{}/stat", base)) {
+        ret.ppid = stat.ppid.and_then(to_pid);
+        ret.pgid = stat.pgrp.and_then(to_pid);
+        ret.sid = stat.session.and_then(to_pid);
+        ret.tty = stat.tty_name();
+        ret.state = stat.state;
+        ret.start_time = stat.starttime;
+        ret.started_at = ret.start_time.and_then(|t| Some(boot_time? + t / clock_ticks()?));
+    }
+    if let Ok(status) = get_str_for(&format!("{}/status", base)) {
+        // each of these lines is the real, effective, saved and file system id
+        let uids = get_ids(&status, "Uid:");
+        ret.uid = uids[0];
+        ret.euid = uids[1];
+        ret.suid = uids[2];
+        let gids = get_ids(&status, "Gid:");
+        ret.gid = gids[0];
+        ret.egid = gids[1];
+        ret.sgid = gids[2];
+        ret.user = ret.uid.and_then(|uid| accounts.user_name(uid)).map(String::from);
+        ret.group = ret.gid.and_then(|gid| accounts.group_name(gid)).map(String::from);
+    }
+    Some(ret)
+}
+
+fn get_ids(status: &str, label: &str) -> [Option<u32>; 4] {
+    let mut ret = [None; 4];
+    if let Some(line) = status.lines().find(|l| l.starts_with(label)) {
+        for (slot, id) in ret.iter_mut().zip(line[label.len()..].split_whitespace()) {
+            *slot = id.parse().ok();
+        }
+    }
+    ret
+}
+
+/// Convert a read result into an optional field value, noting
+/// the field as hidden if we were not allowed to read it
+fn check<T>(res: Result<T, IoError>, field: Field, hidden: &mut Vec<Field>) -> Option<T> {
+    match res {
+        Ok(value) => Some(value),
+        Err(e) => {
+            if e.kind() == ErrorKind::PermissionDenied {
+                hidden.push(field);
+            }
+            None
+        }
+    }
+}
+
+fn get_cmd_line(path: &str) -> Result<(Option<String>, Vec<String>), IoError> {
+    let cmd_line = get_str_for(path)?;
+    let mut all = cmd_line.trim_end_matches('\u{0}').split('\u{0}');
+    let arg0 = all.next().filter(|a| !a.is_empty()).map(String::from);
+    Ok((arg0, all.map(String::from).collect()))
+}
+
+/// Fill in `env` on each of `processes` from the `environ` files in a
+/// directory laid out like `/proc`
+///
+/// This isn't part of `get_processes` since it is often large and may
+/// hold secrets
+pub fn attach_environ(processes: &mut [Process], proc_root: &Path) {
+    for p in processes {
+        let path = proc_root.join(p.pid.to_string()).join("environ");
+        p.env = check(get_environ(&path.to_string_lossy()), Field::Env, &mut p.hidden);
+    }
+}
+
+fn get_environ(path: &str) -> Result<BTreeMap<String, String>, IoError> {
+    // values can start or end with whitespace, so unlike the other
+    // files this one isn't trimmed
+    let environ = read(path)?;
+    Ok(environ.split(|&b| b == 0)
+        .filter(|var| !var.is_empty())
+        .map(|var| {
+            let var = String::from_utf8_lossy(var);
+            let mut parts = var.splitn(2, '=');
+            let key = parts.next().unwrap_or_default().to_string();
+            (key, parts.next().unwrap_or_default().to_string())
+        })
+        .collect())
+}
+
+/// Negative ids are used by the kernel to mean "none"
+fn to_pid(id: i32) -> Option<usize> {
+    if id < 0 {
+        None
+    } else {
+        Some(id as usize)
+    }
+}
+
+/// The time the system booted, in seconds since the unix epoch
+fn get_boot_time(root: &Path) -> Option<u64> {
+    let stat = get_str_for(&root.join("stat").to_string_lossy()).ok()?;
+    stat.lines()
+        .find(|l| l.starts_with("btime "))
+        .and_then(|l| l[6..].trim().parse().ok())
+}
+
+/// The number of clock ticks per second used by the time fields of stat
+pub(crate) fn clock_ticks() -> Option<u64> {
+    let ticks = unsafe { sysconf(_SC_CLK_TCK) };
+    if ticks > 0 {
+        Some(ticks as u64)
+    } else {
+        None
+    }
+}
+
+fn get_str_for(path: &str) -> Result<String, IoError> {
+    // names and arguments aren't guaranteed to be utf8
+    let ret = read(path)?;
+    Ok(String::from_utf8_lossy(&ret).trim().to_string())
+}
+
+pub(crate) fn get_link_for(path: &str, plain_links: bool) -> Result<String, IoError> {
+    match read_link(path) {
+        Ok(link) => Ok(link.to_string_lossy().to_string()),
+        Err(ref e) if plain_links && e.kind() == ErrorKind::InvalidInput => get_str_for(path),
+        Err(e) => Err(e),
+    }
+}
+
+/// One entry of `/proc/<pid>/fd`
+pub(crate) struct FdLink {
+    pub fd: u32,
+    /// The link itself, which can be followed even when the
+    /// file has been deleted
+    pub path: PathBuf,
+    /// What the link points to, like a path or `socket:[1234]`
+    pub target: String,
+}
+
+/// Read every link in the `fd` directory of `pid`, an error means the
+/// directory itself couldn't be read
+pub(crate) fn get_fd_links(proc_root: &Path, pid: usize) -> Result<Vec<FdLink>, IoError> {
+    let mut ret = vec![];
+    for entry in read_dir(proc_root.join(pid.to_string()).join("fd"))? {
+        let path = entry?.path();
+        let fd = match path.file_name().and_then(|n| n.to_str()).and_then(|n| n.parse().ok()) {
+            Some(fd) => fd,
+            None => continue,
+        };
+        // the fd may have been closed since the directory was read
+        if let Ok(target) = get_link_for(&path.to_string_lossy(), true) {
+            ret.push(FdLink { fd, path, target });
+        }
+    }
+    Ok(ret)
+}
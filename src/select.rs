@@ -0,0 +1,45 @@
+use super::{Error, Match};
+
+/// What to do when more than one process matches a lookup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// Exactly one process must match
+    Only,
+    /// Keep every match
+    All,
+    /// Keep the match that started most recently
+    Newest,
+    /// Keep the match that started first
+    Oldest,
+    /// Keep the match with the lowest pid
+    First,
+}
+
+/// Narrow down `matches` according to `selection`, the result will
+/// be sorted by pid
+///
+/// This fails with `Error::Ambiguous` if `selection` is `Only` and
+/// more than one process matched. An empty list is never an error.
+pub fn select(mut matches: Vec<Match>, selection: Selection) -> Result<Vec<Match>, Error> {
+    matches.sort_by_key(|m| m.process.pid);
+    if matches.len() < 2 {
+        return Ok(matches);
+    }
+    let picked = match selection {
+        Selection::All => return Ok(matches),
+        Selection::Only => {
+            return Err(Error::Ambiguous(matches.into_iter().map(|m| m.process).collect()));
+        }
+        Selection::First => 0,
+        // processes without a start time sort before any that have one
+        Selection::Newest => matches.iter().enumerate()
+            .max_by_key(|(_, m)| (m.process.start_time, m.process.pid))
+            .map(|(i, _)| i)
+            .unwrap_or_default(),
+        Selection::Oldest => matches.iter().enumerate()
+            .min_by_key(|(_, m)| (m.process.start_time.is_none(), m.process.start_time, m.process.pid))
+            .map(|(i, _)| i)
+            .unwrap_or_default(),
+    };
+    Ok(vec![matches.swap_remove(picked)])
+}
@@ -0,0 +1,131 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use libc::{self, c_int};
+
+use super::Error;
+use handle::{Handle, Identity};
+
+static NAMES: &[(&str, c_int)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    ("SYS", libc::SIGSYS),
+];
+
+/// A signal that can be sent to a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signal(c_int);
+
+impl Signal {
+    pub const TERM: Signal = Signal(libc::SIGTERM);
+    pub const KILL: Signal = Signal(libc::SIGKILL);
+
+    /// The signal's number, which may differ between platforms
+    pub fn number(self) -> c_int {
+        self.0
+    }
+
+    /// The signal's name without the `SIG` prefix, if it has one
+    pub fn name(self) -> Option<&'static str> {
+        NAMES.iter().find(|&&(_, n)| n == self.0).map(|&(name, _)| name)
+    }
+}
+
+impl FromStr for Signal {
+    type Err = Error;
+    /// Parse a signal number or a name like `TERM`, `SIGTERM` or `term`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(n) = s.parse::<c_int>() {
+            if n < 0 || n > max_signal() {
+                return Err(Error::Invalid(format!("invalid signal number {}", n)));
+            }
+            return Ok(Signal(n));
+        }
+        let upper = s.to_uppercase();
+        let name = upper.trim_start_matches("SIG");
+        NAMES.iter()
+            .find(|&&(n, _)| n == name)
+            .map(|&(_, n)| Signal(n))
+            .ok_or_else(|| Error::Invalid(format!("unknown signal {}", s)))
+    }
+}
+
+/// The highest signal number `kill` accepts, including real-time signals
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn max_signal() -> c_int {
+    libc::SIGRTMAX()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn max_signal() -> c_int {
+    31
+}
+
+impl Display for Signal {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self.name() {
+            Some(name) => name.fmt(f),
+            None => self.0.fmt(f),
+        }
+    }
+}
+
+/// What happened when a signal was sent to a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// The signal was sent
+    Sent,
+    /// We aren't allowed to signal the process, `EPERM`
+    NotPermitted,
+    /// The process no longer exists, `ESRCH`
+    NoSuchProcess,
+}
+
+impl Display for Delivery {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Delivery::Sent => "ok".fmt(f),
+            Delivery::NotPermitted => "EPERM".fmt(f),
+            Delivery::NoSuchProcess => "ESRCH".fmt(f),
+        }
+    }
+}
+
+/// Send `signal` to the process with `identity`
+///
+/// The identity is checked first so a process that has taken over
+/// the pid is never signalled. Failing because of permissions or
+/// because the process is gone is reported as a `Delivery`, anything
+/// else is an error
+pub fn send_signal(identity: Identity, signal: Signal) -> Result<Delivery, Error> {
+    match Handle::open(identity)? {
+        Some(handle) => handle.signal(signal),
+        None => Ok(Delivery::NoSuchProcess),
+    }
+}
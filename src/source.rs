@@ -0,0 +1,164 @@
+use std::path::{Path, PathBuf};
+
+use super::{procfs, Accounts, Error, Process};
+#[cfg(target_os = "macos")]
+use super::mac;
+
+/// Somewhere a list of processes can be read from
+pub trait ProcessSource {
+    /// Get every process this source knows about
+    fn processes(&self) -> Result<Vec<Process>, Error>;
+
+    /// The directory laid out like `/proc` the processes were read
+    /// from, where their sockets, open files and locks can be found
+    fn proc_root(&self) -> Option<&Path> {
+        None
+    }
+}
+
+impl<S: ProcessSource + ?Sized> ProcessSource for &S {
+    fn processes(&self) -> Result<Vec<Process>, Error> {
+        (**self).processes()
+    }
+
+    fn proc_root(&self) -> Option<&Path> {
+        (**self).proc_root()
+    }
+}
+
+impl<S: ProcessSource + ?Sized> ProcessSource for Box<S> {
+    fn processes(&self) -> Result<Vec<Process>, Error> {
+        (**self).processes()
+    }
+
+    fn proc_root(&self) -> Option<&Path> {
+        (**self).proc_root()
+    }
+}
+
+/// The source that reads the processes running on this machine
+#[cfg(not(target_os = "macos"))]
+pub fn native_source() -> ProcFs {
+    ProcFs::default()
+}
+
+/// The source that reads the processes running on this machine
+#[cfg(target_os = "macos")]
+pub fn native_source() -> Sysctl {
+    Sysctl
+}
+
+/// The `/proc` file system or a directory laid out like it, for
+/// example the host's `/proc` mounted into a container
+///
+/// User and group names are read from `/etc`
+#[derive(Debug, Clone)]
+pub struct ProcFs {
+    root: PathBuf,
+    etc: PathBuf,
+}
+
+impl ProcFs {
+    pub fn new<P: Into<PathBuf>>(root: P) -> ProcFs {
+        ProcFs {
+            root: root.into(),
+            etc: PathBuf::from("/etc"),
+        }
+    }
+
+    /// Read user and group names from `etc` instead of `/etc`
+    pub fn etc<P: Into<PathBuf>>(mut self, etc: P) -> ProcFs {
+        self.etc = etc.into();
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Default for ProcFs {
+    fn default() -> ProcFs {
+        ProcFs::new("/proc")
+    }
+}
+
+impl ProcessSource for ProcFs {
+    fn processes(&self) -> Result<Vec<Process>, Error> {
+        procfs::get_processes(&self.root, false, &Accounts::load(&self.etc))
+    }
+
+    fn proc_root(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+/// A directory of files laid out like `/proc` that is easy to check
+/// in or hand edit, links like `exe` can either be symlinks or
+/// regular files containing the path they would point to
+///
+/// User and group names are read from `etc/passwd` and `etc/group`
+/// inside the fixture, if they exist
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    root: PathBuf,
+}
+
+impl Fixture {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Fixture {
+        Fixture { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl ProcessSource for Fixture {
+    fn processes(&self) -> Result<Vec<Process>, Error> {
+        procfs::get_processes(&self.root, true, &Accounts::load(self.root.join("etc")))
+    }
+
+    fn proc_root(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+/// A fixed list of processes
+#[derive(Debug, Clone, Default)]
+pub struct InMemory {
+    processes: Vec<Process>,
+}
+
+impl InMemory {
+    pub fn new(processes: Vec<Process>) -> InMemory {
+        InMemory { processes }
+    }
+
+    pub fn push(&mut self, process: Process) {
+        self.processes.push(process);
+    }
+}
+
+impl ProcessSource for InMemory {
+    fn processes(&self) -> Result<Vec<Process>, Error> {
+        Ok(self.processes.clone())
+    }
+}
+
+/// The processes running on this machine, as reported by `sysctl`
+#[cfg(target_os = "macos")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sysctl;
+
+#[cfg(target_os = "macos")]
+impl ProcessSource for Sysctl {
+    fn processes(&self) -> Result<Vec<Process>, Error> {
+        let tups = mac::get_processes()?;
+        Ok(tups.into_iter().map(|(pid, cmd)| Process {
+            pid,
+            cmd: Some(cmd),
+            ..Process::default()
+        }).collect())
+    }
+}
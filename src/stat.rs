@@ -0,0 +1,184 @@
+//! Parsing for `/proc/<pid>/stat`
+//!
+//! The second field of a stat file is the process' name wrapped in parens,
+//! but the name can itself contain spaces and parens, for example `(sd-pam)`
+//! or `a) b (c`. The kernel never escapes it, so the only reliable way to
+//! find its end is the last `)` in the file. Everything after that is
+//! space separated.
+//!
+//! Each field is parsed on its own, a missing or malformed field is `None`
+//! and never prevents the rest of the fields from being read.
+use std::fs::read;
+use std::io::Error as IoError;
+use std::path::Path;
+
+macro_rules! stat_fields {
+    ($($(#[$doc:meta])* $name:ident: $ty:ty,)*) => {
+        /// The fields of a stat file, named and typed as documented in `proc(5)`
+        #[derive(Debug, Clone, Default, PartialEq, Serialize)]
+        pub struct Stat {
+            /// (1) The process id
+            pub pid: Option<i32>,
+            /// (2) The name of the executable, without the surrounding parens
+            pub comm: Option<String>,
+            $($(#[$doc])* pub $name: Option<$ty>,)*
+        }
+
+        impl Stat {
+            /// Fill in every field after `comm` from `fields`, in order
+            fn parse_fields<'a, I: Iterator<Item = &'a str>>(&mut self, mut fields: I) {
+                $(self.$name = fields.next().and_then(|f| f.parse().ok());)*
+            }
+        }
+    };
+}
+
+stat_fields! {
+    /// (3) One of R, S, D, Z, T, t, X, I and a few others on older kernels
+    state: char,
+    /// (4) The pid of the parent
+    ppid: i32,
+    /// (5) The process group id
+    pgrp: i32,
+    /// (6) The session id
+    session: i32,
+    /// (7) The controlling terminal's device number
+    tty_nr: i32,
+    /// (8) The foreground process group of the controlling terminal
+    tpgid: i32,
+    /// (9) The kernel flags word
+    flags: u32,
+    /// (10) Minor faults that didn't require loading a page
+    minflt: u64,
+    /// (11) Minor faults of waited-for children
+    cminflt: u64,
+    /// (12) Major faults that required loading a page
+    majflt: u64,
+    /// (13) Major faults of waited-for children
+    cmajflt: u64,
+    /// (14) Time spent in user mode, in clock ticks
+    utime: u64,
+    /// (15) Time spent in kernel mode, in clock ticks
+    stime: u64,
+    /// (16) Time waited-for children spent in user mode, in clock ticks
+    cutime: i64,
+    /// (17) Time waited-for children spent in kernel mode, in clock ticks
+    cstime: i64,
+    /// (18) The scheduling priority
+    priority: i64,
+    /// (19) The nice value, from 19 (lowest priority) to -20 (highest)
+    nice: i64,
+    /// (20) The number of threads
+    num_threads: i64,
+    /// (21) Always 0 since Linux 2.6.17
+    itrealvalue: i64,
+    /// (22) The time the process started, in clock ticks since boot
+    starttime: u64,
+    /// (23) Virtual memory size in bytes
+    vsize: u64,
+    /// (24) Resident set size in pages
+    rss: i64,
+    /// (25) The soft limit on the resident set size in bytes
+    rsslim: u64,
+    /// (26) The address above which program text can run
+    startcode: u64,
+    /// (27) The address below which program text can run
+    endcode: u64,
+    /// (28) The address of the start of the stack
+    startstack: u64,
+    /// (29) The current value of the stack pointer
+    kstkesp: u64,
+    /// (30) The current instruction pointer
+    kstkeip: u64,
+    /// (31) The bitmap of pending signals, obsolete
+    signal: u64,
+    /// (32) The bitmap of blocked signals, obsolete
+    blocked: u64,
+    /// (33) The bitmap of ignored signals, obsolete
+    sigignore: u64,
+    /// (34) The bitmap of caught signals, obsolete
+    sigcatch: u64,
+    /// (35) The channel the process is waiting in, 0 or 1 on newer kernels
+    wchan: u64,
+    /// (36) Not maintained
+    nswap: u64,
+    /// (37) Not maintained
+    cnswap: u64,
+    /// (38) The signal sent to the parent when this process dies
+    exit_signal: i32,
+    /// (39) The CPU the process last ran on
+    processor: i32,
+    /// (40) The real-time scheduling priority
+    rt_priority: u32,
+    /// (41) The scheduling policy
+    policy: u32,
+    /// (42) Aggregated block I/O delays, in clock ticks
+    delayacct_blkio_ticks: u64,
+    /// (43) Time spent running a virtual CPU for a guest, in clock ticks
+    guest_time: u64,
+    /// (44) Guest time of waited-for children, in clock ticks
+    cguest_time: i64,
+    /// (45) The address above which initialized and uninitialized data are placed
+    start_data: u64,
+    /// (46) The address below which initialized and uninitialized data are placed
+    end_data: u64,
+    /// (47) The address above which the heap can be expanded
+    start_brk: u64,
+    /// (48) The address above which the command line arguments are placed
+    arg_start: u64,
+    /// (49) The address below which the command line arguments are placed
+    arg_end: u64,
+    /// (50) The address above which the environment is placed
+    env_start: u64,
+    /// (51) The address below which the environment is placed
+    env_end: u64,
+    /// (52) The exit status, as reported by waitpid
+    exit_code: i32,
+}
+
+impl Stat {
+    /// Read and parse a stat file, this only fails if
+    /// the file itself can't be read
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Stat, IoError> {
+        let bytes = read(path)?;
+        Ok(Stat::parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    /// Parse the contents of a stat file, this never fails but any
+    /// field that couldn't be found or parsed will be `None`
+    pub fn parse(text: &str) -> Stat {
+        let mut ret = Stat::default();
+        let (open, close) = match (text.find('('), text.rfind(')')) {
+            (Some(open), Some(close)) if open < close => (open, close),
+            // without a name we can't know where the other fields are
+            // so only the pid can be trusted
+            _ => {
+                ret.pid = text.split_whitespace().next().and_then(|p| p.parse().ok());
+                return ret;
+            }
+        };
+        ret.pid = text[..open].trim().parse().ok();
+        ret.comm = Some(text[open + 1..close].to_string());
+        ret.parse_fields(text[close + 1..].split_whitespace());
+        ret
+    }
+
+    /// The name of the controlling terminal under `/dev`, like
+    /// `pts/0` or `tty1`, if there is one
+    pub fn tty_name(&self) -> Option<String> {
+        let tty_nr = self.tty_nr? as u32;
+        if tty_nr == 0 {
+            return None;
+        }
+        let major = (tty_nr >> 8) & 0xfff;
+        let minor = (tty_nr & 0xff) | ((tty_nr >> 12) & 0xfff00);
+        Some(match major {
+            4 if minor < 64 => format!("tty{}", minor),
+            4 => format!("ttyS{}", minor - 64),
+            5 if minor == 0 => "tty".to_string(),
+            5 if minor == 1 => "console".to_string(),
+            136..=143 => format!("pts/{}", (major - 136) * 256 + minor),
+            _ => format!("{}:{}", major, minor),
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use serde_json::{self, Value};
+
+use super::{Error, Process};
+
+/// A user provided output format like `{pid}\t{cmd}\t{args}`
+///
+/// Any field of a `Process` can be used by name, `exe` is accepted
+/// as a shorter name for `full_cmd_path`. The usual backslash escapes
+/// are supported and `{{` or `}}` produce a literal brace.
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Field(String),
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, Error> {
+        let fields = field_names()?;
+        let mut parts = vec![];
+        let mut current = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => current.push('\t'),
+                    Some('n') => current.push('\n'),
+                    Some('r') => current.push('\r'),
+                    Some('0') => current.push('\u{0}'),
+                    Some('\\') => current.push('\\'),
+                    Some(other) => {
+                        current.push('\\');
+                        current.push(other);
+                    }
+                    None => current.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    current.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    current.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(Error::Invalid(format!("unterminated field {{{} in template", name)));
+                    }
+                    let name = match name.trim() {
+                        "exe" => "full_cmd_path",
+                        other => other,
+                    };
+                    if !fields.iter().any(|f| f == name) {
+                        return Err(Error::Invalid(format!("unknown field {{{}}} in template, expected one of {}", name, fields.join(", "))));
+                    }
+                    if !current.is_empty() {
+                        parts.push(Part::Text(current.split_off(0)));
+                    }
+                    parts.push(Part::Field(name.to_string()));
+                }
+                _ => current.push(c),
+            }
+        }
+        if !current.is_empty() {
+            parts.push(Part::Text(current));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Fill in this template with the fields of `process`, missing
+    /// fields are left empty
+    pub fn render(&self, process: &Process) -> Result<String, Error> {
+        let value = serde_json::to_value(process)?;
+        let mut ret = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => ret.push_str(text),
+                Part::Field(name) => ret.push_str(&format_value(&value[name.as_str()])),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+fn field_names() -> Result<Vec<String>, Error> {
+    match serde_json::to_value(Process::default())? {
+        Value::Object(map) => Ok(map.keys().cloned().collect()),
+        _ => Ok(vec![]),
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => escape_control(s),
+        Value::Array(a) => a.iter().map(|v| match v {
+            Value::String(s) => shell_quote(s),
+            other => format_value(other),
+        }).collect::<Vec<_>>().join(" "),
+        other => other.to_string(),
+    }
+}
+
+/// Replace any control characters in `s` with a backslash escape
+/// so a value can't break up the lines or columns of the output
+pub(crate) fn escape_control(s: &str) -> String {
+    let mut ret = String::new();
+    for c in s.chars() {
+        match c {
+            '\t' => ret.push_str("\\t"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\u{0}' => ret.push_str("\\0"),
+            c if c.is_control() => ret.push_str(&format!("\\x{:02x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Quote `s` so that it would be read back as a single word by a
+/// POSIX shell, using `$'...'` if it contains control characters
+pub(crate) fn shell_quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        s.to_string()
+    } else if s.chars().any(|c| c.is_control()) {
+        format!("$'{}'", escape_control(&s.replace('\\', "\\\\").replace('\'', "\\'")))
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
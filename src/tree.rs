@@ -0,0 +1,155 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::str::FromStr;
+
+use serde_json;
+
+use super::{Error, Process};
+
+/// A process and every process it started, recursively
+#[derive(Debug, Clone, Serialize)]
+pub struct Tree {
+    pub process: Process,
+    pub children: Vec<Tree>,
+}
+
+impl Tree {
+    /// Build the tree of every descendant of `pid` out of `processes`
+    pub fn descendants(processes: &[Process], pid: usize) -> Option<Tree> {
+        let mut children: HashMap<usize, Vec<&Process>> = HashMap::new();
+        for p in processes {
+            if let Some(ppid) = p.ppid {
+                children.entry(ppid).or_default().push(p);
+            }
+        }
+        let root = processes.iter().find(|p| p.pid == pid)?;
+        let mut seen = HashSet::new();
+        Some(Tree::build(root, &children, &mut seen))
+    }
+
+    fn build(process: &Process, children: &HashMap<usize, Vec<&Process>>, seen: &mut HashSet<usize>) -> Tree {
+        seen.insert(process.pid);
+        let mut ret = Tree {
+            process: process.clone(),
+            children: vec![],
+        };
+        if let Some(kids) = children.get(&process.pid) {
+            for kid in kids {
+                // a pid can be its own ancestor in a snapshot taken while
+                // pids were being reused, so don't go around forever
+                if !seen.contains(&kid.pid) {
+                    ret.children.push(Tree::build(kid, children, seen));
+                }
+            }
+        }
+        ret
+    }
+
+    /// Build the chain of parents from the oldest known ancestor of
+    /// `pid`, usually pid 1, down to `pid` itself
+    pub fn ancestors(processes: &[Process], pid: usize) -> Option<Tree> {
+        let by_pid: HashMap<usize, &Process> = processes.iter().map(|p| (p.pid, p)).collect();
+        let mut current = *by_pid.get(&pid)?;
+        let mut tree = Tree {
+            process: current.clone(),
+            children: vec![],
+        };
+        let mut seen = HashSet::new();
+        seen.insert(pid);
+        while let Some(parent) = current.ppid.and_then(|ppid| by_pid.get(&ppid)) {
+            if !seen.insert(parent.pid) {
+                break;
+            }
+            tree = Tree {
+                process: (*parent).clone(),
+                children: vec![tree],
+            };
+            current = parent;
+        }
+        Some(tree)
+    }
+}
+
+/// How a list of trees should be written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeFormat {
+    /// Indented lines, like `pstree`
+    Ascii,
+    /// A JSON array of nested objects
+    Json,
+    /// A graphviz digraph
+    Dot,
+}
+
+impl FromStr for TreeFormat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(TreeFormat::Ascii),
+            "json" => Ok(TreeFormat::Json),
+            "dot" => Ok(TreeFormat::Dot),
+            _ => Err(Error::Invalid(format!("unknown tree format {}", s))),
+        }
+    }
+}
+
+/// Write `trees` to `out` in the provided format
+pub fn write_trees<W: Write>(out: &mut W, trees: &[Tree], format: TreeFormat) -> Result<(), Error> {
+    match format {
+        TreeFormat::Ascii => {
+            for (i, tree) in trees.iter().enumerate() {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "{}", label(&tree.process))?;
+                write_ascii(out, &tree.children, "")?;
+            }
+        }
+        TreeFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, trees)?;
+            writeln!(out)?;
+        }
+        TreeFormat::Dot => {
+            writeln!(out, "digraph processes {{")?;
+            let mut seen = HashSet::new();
+            for tree in trees {
+                write_dot(out, tree, &mut seen)?;
+            }
+            writeln!(out, "}}")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_ascii<W: Write>(out: &mut W, children: &[Tree], prefix: &str) -> Result<(), Error> {
+    for (i, child) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let (branch, indent) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+        writeln!(out, "{}{}{}", prefix, branch, label(&child.process))?;
+        write_ascii(out, &child.children, &format!("{}{}", prefix, indent))?;
+    }
+    Ok(())
+}
+
+fn write_dot<W: Write>(out: &mut W, tree: &Tree, seen: &mut HashSet<(usize, usize)>) -> Result<(), Error> {
+    let pid = tree.process.pid;
+    // trees for several matches can share processes, only list them once
+    if seen.insert((pid, pid)) {
+        writeln!(out, "    \"{}\" [label=\"{}\"];", pid, dot_escape(&label(&tree.process)))?;
+    }
+    for child in &tree.children {
+        if seen.insert((pid, child.process.pid)) {
+            writeln!(out, "    \"{}\" -> \"{}\";", pid, child.process.pid)?;
+        }
+        write_dot(out, child, seen)?;
+    }
+    Ok(())
+}
+
+fn label(p: &Process) -> String {
+    format!("{} {}", p.pid, p.display_command())
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
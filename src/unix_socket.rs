@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{canonicalize, read_to_string};
+use std::io::ErrorKind;
+use std::path::Path;
+
+use super::{Error, Field, Process};
+use net::socket_inodes;
+#[cfg(target_os = "linux")]
+use netlink::unix_peers;
+
+/// The flag set on a socket that is accepting connections
+const ACCEPTING: u32 = 0x10000;
+
+/// How a process is using a unix socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Waiting for connections on the path
+    Listening,
+    /// The server's end of a connection made to the path
+    Accepted,
+    /// The client's end of a connection made to the path
+    Connected,
+}
+
+/// A unix socket a process holds that belongs to the path looked up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnixSocket {
+    pub role: Role,
+    /// The path the socket is bound or connected to, abstract
+    /// sockets start with `@`
+    pub path: String,
+    pub inode: u64,
+}
+
+/// One line of `/proc/net/unix`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixEntry {
+    pub inode: u64,
+    pub accepting: bool,
+    pub path: Option<String>,
+}
+
+/// Parse the text of `/proc/net/unix`, lines that can't be read are skipped
+pub fn parse_unix(text: &str) -> Vec<UnixEntry> {
+    text.lines().skip(1).filter_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+        Some(UnixEntry {
+            inode: fields.get(6)?.parse().ok()?,
+            accepting: flags & ACCEPTING != 0,
+            path: path_of(line).map(str::to_string),
+        })
+    }).collect()
+}
+
+/// The path at the end of a line, kept as it is since it can contain
+/// runs of spaces, the fields before it are padded so can't be used to
+/// find where it starts
+fn path_of(line: &str) -> Option<&str> {
+    let mut rest = line.trim_start();
+    for _ in 0..6 {
+        rest = rest[rest.find(' ')?..].trim_start();
+    }
+    let inode_end = rest.find(' ')?;
+    Some(&rest[inode_end + 1..]).filter(|p| !p.is_empty())
+}
+
+/// A unix socket path to look for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixTarget {
+    paths: Vec<String>,
+}
+
+impl UnixTarget {
+    /// The path is matched as given and, if it exists, with any
+    /// symlinks resolved
+    pub fn new(path: &str) -> UnixTarget {
+        let mut paths = vec![path.to_string()];
+        if let Ok(resolved) = canonicalize(path) {
+            let resolved = resolved.to_string_lossy().to_string();
+            if resolved != path {
+                paths.push(resolved);
+            }
+        }
+        UnixTarget { paths }
+    }
+
+    pub fn matches(&self, socket: &UnixSocket) -> bool {
+        self.paths.contains(&socket.path)
+    }
+}
+
+/// Fill in `unix_sockets` on each of `processes` that is listening on
+/// `target` or has accepted a connection on it, and when reading the
+/// real `/proc` on each process connected to it
+///
+/// Finding the clients means asking the kernel for each socket's peer,
+/// if that isn't possible only the server's side is found
+pub fn attach_unix_sockets(processes: &mut [Process], proc_root: &Path, target: &UnixTarget) -> Result<(), Error> {
+    let text = read_to_string(proc_root.join("net").join("unix"))?;
+    let mut sockets: HashMap<u64, UnixSocket> = HashMap::new();
+    for entry in parse_unix(&text) {
+        if let Some(path) = entry.path {
+            if target.paths.contains(&path) {
+                let role = if entry.accepting { Role::Listening } else { Role::Accepted };
+                sockets.insert(entry.inode, UnixSocket { role, path, inode: entry.inode });
+            }
+        }
+    }
+    if sockets.is_empty() {
+        return Ok(());
+    }
+    if proc_root == Path::new("/proc") {
+        let accepted: HashSet<u64> = sockets.values()
+            .filter(|s| s.role == Role::Accepted)
+            .map(|s| s.inode)
+            .collect();
+        for (inode, peer) in peers() {
+            if accepted.contains(&inode) {
+                let path = sockets[&inode].path.clone();
+                sockets.insert(peer, UnixSocket { role: Role::Connected, path, inode: peer });
+            }
+        }
+    }
+    for p in processes {
+        match socket_inodes(proc_root, p.pid) {
+            Ok(inodes) => {
+                let held: Vec<_> = inodes.iter().filter_map(|i| sockets.get(i).cloned()).collect();
+                if !held.is_empty() {
+                    p.unix_sockets = Some(held);
+                }
+            }
+            Err(Error::Io(ref e)) if e.kind() == ErrorKind::PermissionDenied => p.hidden.push(Field::UnixSockets),
+            Err(_) => (),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn peers() -> HashMap<u64, u64> {
+    unix_peers().unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peers() -> HashMap<u64, u64> {
+    HashMap::new()
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+
+use super::Error;
+
+/// The user and group names of a system, read from
+/// its `passwd` and `group` files
+#[derive(Debug, Clone, Default)]
+pub struct Accounts {
+    users: IdMap,
+    groups: IdMap,
+}
+
+impl Accounts {
+    /// Read `passwd` and `group` from `etc`, a missing or
+    /// unreadable file is treated as empty
+    pub fn load<P: AsRef<Path>>(etc: P) -> Accounts {
+        let etc = etc.as_ref();
+        Accounts {
+            users: IdMap::load(&etc.join("passwd")),
+            groups: IdMap::load(&etc.join("group")),
+        }
+    }
+
+    /// Build from the text of a `passwd` and a `group` file
+    pub fn parse(passwd: &str, group: &str) -> Accounts {
+        Accounts {
+            users: IdMap::parse(passwd),
+            groups: IdMap::parse(group),
+        }
+    }
+
+    pub fn user_name(&self, uid: u32) -> Option<&str> {
+        self.users.name(uid)
+    }
+
+    pub fn group_name(&self, gid: u32) -> Option<&str> {
+        self.groups.name(gid)
+    }
+
+    /// Look up a user given either by name or by number
+    pub fn user_id(&self, user: &str) -> Result<u32, Error> {
+        self.users.id(user).ok_or_else(|| Error::Invalid(format!("unknown user {}", user)))
+    }
+
+    /// Look up a group given either by name or by number
+    pub fn group_id(&self, group: &str) -> Result<u32, Error> {
+        self.groups.id(group).ok_or_else(|| Error::Invalid(format!("unknown group {}", group)))
+    }
+}
+
+/// The names and ids from a file laid out like `/etc/passwd`
+/// or `/etc/group`, where each line starts with `name:password:id`
+#[derive(Debug, Clone, Default)]
+struct IdMap {
+    names: HashMap<u32, String>,
+    ids: HashMap<String, u32>,
+}
+
+impl IdMap {
+    fn load(path: &Path) -> IdMap {
+        read_to_string(path).map(|text| IdMap::parse(&text)).unwrap_or_default()
+    }
+
+    fn parse(text: &str) -> IdMap {
+        let mut ret = IdMap::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split(':');
+            let name = match parts.next() {
+                Some(name) if !name.is_empty() => name,
+                _ => continue,
+            };
+            let id = match parts.nth(1).and_then(|id| id.parse().ok()) {
+                Some(id) => id,
+                None => continue,
+            };
+            // the first entry wins, the same as getpwuid
+            ret.names.entry(id).or_insert_with(|| name.to_string());
+            ret.ids.entry(name.to_string()).or_insert(id);
+        }
+        ret
+    }
+
+    fn name(&self, id: u32) -> Option<&str> {
+        self.names.get(&id).map(|n| n.as_str())
+    }
+
+    fn id(&self, name_or_id: &str) -> Option<u32> {
+        self.ids.get(name_or_id).cloned().or_else(|| name_or_id.parse().ok())
+    }
+}
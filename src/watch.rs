@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::Process;
+use handle::Identity;
+use procfs::clock_ticks;
+use time::{format_timestamp, uptime};
+
+/// Whether a process started or exited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Start,
+    Exit,
+}
+
+/// A change seen between two `Snapshot`s
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub event: EventKind,
+    /// When the process started or when its exit was seen, in
+    /// seconds since the unix epoch
+    pub time: Option<u64>,
+    /// How many milliseconds the process ran for, only set on exits
+    pub lifetime_ms: Option<u64>,
+    pub process: Process,
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let time = self.time.map(format_timestamp).unwrap_or_else(|| "?".to_string());
+        let command = self.process.display_command();
+        match self.event {
+            EventKind::Start => write!(f, "{}  start  {}  {}", time, self.process.pid, command),
+            EventKind::Exit => {
+                write!(f, "{}  exit   {}  {}", time, self.process.pid, command)?;
+                if let Some(ms) = self.lifetime_ms {
+                    write!(f, " after {}.{:03}s", ms / 1000, ms % 1000)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The processes running at one point in time, keyed by their
+/// `Identity` so a reused pid shows up as an exit and a start
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    processes: HashMap<Identity, Process>,
+}
+
+impl Snapshot {
+    /// Zombies in `processes` are left out, they have already exited
+    pub fn new(processes: Vec<Process>) -> Snapshot {
+        Snapshot {
+            processes: processes.into_iter()
+                .filter(|p| !p.has_exited())
+                .map(|p| (p.identity(), p))
+                .collect(),
+        }
+    }
+
+    /// The process with `identity`, if it was running
+    pub fn get(&self, identity: Identity) -> Option<&Process> {
+        self.processes.get(&identity)
+    }
+
+    /// The processes that started or exited between this snapshot and
+    /// `newer`, which was taken at `now`, ordered by pid
+    ///
+    /// Lifetimes are measured up to the moment `diff` is called
+    pub fn diff(&self, newer: &Snapshot, now: SystemTime) -> Vec<Event> {
+        let now = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).ok();
+        let uptime = uptime();
+        let mut events: Vec<Event> = self.processes.iter()
+            .filter(|&(id, _)| !newer.processes.contains_key(id))
+            .map(|(_, p)| Event {
+                event: EventKind::Exit,
+                time: now,
+                lifetime_ms: uptime.and_then(|uptime| lifetime(uptime, p.start_time?)),
+                process: p.clone(),
+            })
+            .chain(newer.processes.iter()
+                .filter(|&(id, _)| !self.processes.contains_key(id))
+                .map(|(_, p)| Event {
+                    event: EventKind::Start,
+                    time: p.started_at.or(now),
+                    lifetime_ms: None,
+                    process: p.clone(),
+                }))
+            .collect();
+        events.sort_by_key(|e| (e.process.pid, e.event == EventKind::Start));
+        events
+    }
+}
+
+/// The milliseconds between a start time in clock ticks since boot
+/// and `uptime`
+fn lifetime(uptime: Duration, start_time: u64) -> Option<u64> {
+    let ticks = clock_ticks()?;
+    let started = start_time.checked_mul(1000)? / ticks;
+    Some((uptime.as_millis() as u64).saturating_sub(started))
+}
@@ -0,0 +1,59 @@
+use std::process::{Command, Output};
+
+const FIXTURE: &str = "tests/fixtures/proc";
+
+fn getpid(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_getpid")).args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn lookup() {
+    let output = getpid(&["--proc-root", FIXTURE, "--first", "fakesvc"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "42\n");
+}
+
+#[test]
+fn subcommands_with_only_a_filter() {
+    let output = getpid(&["tree", "--proc-root", FIXTURE, "--parent", "42"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "43 fakesvc --worker\n");
+    let output = getpid(&["ancestors", "--proc-root", FIXTURE, "--parent", "42"]);
+    assert_eq!(stdout(&output), "\
+1 /sbin/init
+└── 42 /usr/bin/fakesvc --config=/etc/fake.toml
+    └── 43 fakesvc --worker
+");
+}
+
+#[test]
+fn subcommand_names_are_not_process_names() {
+    let output = getpid(&["kill", "-n", "--session", "999999999"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "no process found for session 999999999\n");
+}
+
+#[test]
+fn subcommands_need_a_name_or_filter() {
+    for command in &["tree", "ancestors", "kill"] {
+        let output = getpid(&[command]);
+        assert_eq!(output.status.code(), Some(2), "{}", command);
+        assert!(stdout(&output).is_empty());
+    }
+}
+
+#[test]
+fn names_from_etc_root() {
+    let etc = format!("{}/etc", FIXTURE);
+    let output = getpid(&["--proc-root", FIXTURE, "--etc-root", &etc, "--user", "app", "--all", "--output", "{pid} {user} {group}"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "42 app app\n43 app app\n");
+}
@@ -0,0 +1,44 @@
+extern crate getpid;
+
+use std::time::Duration;
+
+use getpid::{Error, Escalation, Signal, Step};
+
+fn invalid(text: &str) -> String {
+    match text.parse::<Escalation>() {
+        Err(Error::Invalid(msg)) => msg,
+        other => panic!("expected Error::Invalid for {}, found {:?}", text, other),
+    }
+}
+
+#[test]
+fn steps_in_order() {
+    let plan: Escalation = "TERM:10s,INT:500ms,KILL".parse().unwrap();
+    assert_eq!(plan.steps(), &[
+        Step { signal: Signal::TERM, wait: Duration::from_secs(10) },
+        Step { signal: "INT".parse().unwrap(), wait: Duration::from_millis(500) },
+        Step { signal: Signal::KILL, wait: Duration::from_secs(5) },
+    ]);
+}
+
+#[test]
+fn default_wait() {
+    let plan: Escalation = "15".parse().unwrap();
+    assert_eq!(plan, Escalation::new(vec![Step { signal: Signal::TERM, wait: Duration::from_secs(5) }]));
+    // empty entries between commas are ignored
+    assert_eq!("TERM,,".parse::<Escalation>().unwrap(), plan);
+}
+
+#[test]
+fn bad_steps() {
+    assert_eq!(invalid("TERM,NOPE:1s"), "unknown signal NOPE");
+    assert_eq!(invalid("TERM:soon"), "invalid duration soon");
+    assert_eq!(invalid("TERM:"), "invalid duration ");
+    assert_eq!(invalid(":1s"), "unknown signal ");
+}
+
+#[test]
+fn empty_plan() {
+    assert_eq!(invalid(""), "no signals in ");
+    assert_eq!(invalid(",,"), "no signals in ,,");
+}
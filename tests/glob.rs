@@ -0,0 +1,63 @@
+extern crate getpid;
+
+use getpid::{Mode, Pattern};
+
+fn glob(text: &str) -> Pattern {
+    Pattern::new(text, Mode::Glob, false).unwrap()
+}
+
+#[test]
+fn star_and_question_mark() {
+    let p = glob("ng*x-?");
+    assert!(p.is_match("nginx-1"));
+    assert!(p.is_match("ngx-a"));
+    assert!(!p.is_match("nginx-12"));
+    assert!(!p.is_match("my-nginx-1"));
+}
+
+#[test]
+fn negated_class() {
+    let p = glob("worker-[!a]");
+    assert!(p.is_match("worker-b"));
+    assert!(p.is_match("worker-1"));
+    assert!(!p.is_match("worker-a"));
+    assert!(!p.is_match("worker-"));
+}
+
+#[test]
+fn close_bracket_first_in_class() {
+    let p = glob("x[]]");
+    assert!(p.is_match("x]"));
+    assert!(!p.is_match("x["));
+    let p = glob("x[!]]");
+    assert!(p.is_match("xa"));
+    assert!(!p.is_match("x]"));
+}
+
+#[test]
+fn unterminated_class_is_literal() {
+    let p = glob("foo[bar");
+    assert!(p.is_match("foo[bar"));
+    assert!(!p.is_match("foob"));
+    let p = glob("a[*");
+    assert!(p.is_match("a["));
+    assert!(p.is_match("a[bc"));
+}
+
+#[test]
+fn regex_metacharacters_are_literal() {
+    let p = glob("a.b+c(d)|e^$\\{1}");
+    assert!(p.is_match("a.b+c(d)|e^$\\{1}"));
+    assert!(!p.is_match("axb+c(d)|e^$\\{1}"));
+    assert!(!p.is_match("a.bbc(d)|e^$\\{1}"));
+}
+
+#[test]
+fn class_with_regex_metacharacters() {
+    let p = glob("v[.\\&~]");
+    assert!(p.is_match("v."));
+    assert!(p.is_match("v\\"));
+    assert!(p.is_match("v&"));
+    assert!(p.is_match("v~"));
+    assert!(!p.is_match("vx"));
+}
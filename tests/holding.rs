@@ -0,0 +1,57 @@
+#![cfg(target_os = "linux")]
+
+extern crate getpid;
+extern crate libc;
+
+use std::fs::{metadata, read_to_string};
+use std::os::unix::fs::MetadataExt;
+
+use getpid::{parse_maps, Held, Target, Use};
+
+const MAPS: &str = "\
+55b19d066000-55b19d068000 r--p 00000000 fe:00 317783                     /usr/bin/head
+55b19d068000-55b19d06e000 r-xp 00002000 fe:00 317783                     /usr/bin/head
+55b19d6a4000-55b19d6c5000 rw-p 00000000 00:00 0                          [heap]
+7f2c1b200000-7f2c1b228000 r--p 00000000 103:10003 2097                   /usr/lib/x86_64-linux-gnu/libc.so.6
+7f2c1b5d0000-7f2c1b5d1000 rw-s 00000000 1234:05 88                       /tmp/with space (deleted)
+7ffd3c1e5000-7ffd3c206000 rw-p 00000000 00:00 0                          [stack]
+";
+
+fn held(path: &str, device: u64, inode: u64) -> Held {
+    Held {
+        how: Use::Map,
+        fd: None,
+        path: path.to_string(),
+        device,
+        inode,
+    }
+}
+
+#[test]
+fn one_entry_per_file() {
+    assert_eq!(parse_maps(MAPS), vec![
+        held("/usr/bin/head", libc::makedev(0xfe, 0), 317783),
+        held("/usr/lib/x86_64-linux-gnu/libc.so.6", libc::makedev(0x103, 0x10003), 2097),
+        held("/tmp/with space (deleted)", libc::makedev(0x1234, 0x05), 88),
+    ]);
+}
+
+#[test]
+fn anonymous_maps_are_skipped() {
+    let anonymous = "7ffd3c1e5000-7ffd3c206000 rw-p 00000000 00:00 0\n\
+        7ffd3c3e2000-7ffd3c3e4000 r-xp 00000000 00:00 0                  [vdso]\n";
+    assert!(parse_maps(anonymous).is_empty());
+}
+
+#[test]
+fn mapped_files_match_their_target() {
+    let maps = parse_maps(&read_to_string("/proc/self/maps").unwrap());
+    let exe = std::env::current_exe().unwrap();
+    let meta = metadata(&exe).unwrap();
+    let mapped = maps.iter()
+        .find(|h| h.inode == meta.ino() && h.path == exe.to_string_lossy())
+        .expect("the test binary should be mapped");
+    assert!(Target::new(&exe, false).unwrap().matches(mapped));
+    assert!(Target::new(exe.parent().unwrap(), true).unwrap().matches(mapped));
+    assert!(!Target::new("/proc/self/maps", false).unwrap().matches(mapped));
+}
@@ -0,0 +1,99 @@
+#![cfg(target_os = "linux")]
+
+extern crate getpid;
+extern crate libc;
+
+use std::env::temp_dir;
+use std::fs::{copy, create_dir_all, metadata, read_to_string, remove_dir_all, write, File, OpenOptions};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+
+use getpid::{find_in, parse_locks, Fixture, Lock, LockKind, LockMode, Matcher, Mode, Pattern, Target};
+
+const LOCKS: &str = "\
+1: FLOCK  ADVISORY  READ 20719 fe:00:1220728 0 EOF
+2: POSIX  ADVISORY  WRITE 20719 fe:00:1220728 0 EOF
+2: -> POSIX  ADVISORY  WRITE 20722 fe:00:1220728 0 EOF
+2:  -> OFDLCK ADVISORY  READ -1 fe:00:1220728 10 29
+3: OFDLCK ADVISORY  WRITE -1 fe:00:1220660 0 EOF
+";
+
+#[test]
+fn locks_with_a_pid() {
+    let lock = |kind, waiting, pid| Lock {
+        kind,
+        mode: LockMode::Write,
+        mandatory: false,
+        waiting,
+        pid,
+        device: libc::makedev(0xfe, 0),
+        inode: 1220728,
+        start: 0,
+        end: None,
+    };
+    let mut flock = lock(LockKind::Flock, false, 20719);
+    flock.mode = LockMode::Read;
+    // open file description locks never have one
+    assert_eq!(parse_locks(LOCKS), vec![
+        flock,
+        lock(LockKind::Posix, false, 20719),
+        lock(LockKind::Posix, true, 20722),
+    ]);
+}
+
+#[test]
+fn byte_ranges() {
+    let text = "1: POSIX  MANDATORY WRITE 5 08:01:12 100 199\n";
+    let lock = &parse_locks(text)[0];
+    assert!(lock.mandatory);
+    assert_eq!((lock.start, lock.end), (100, Some(199)));
+}
+
+/// Take an open file description lock and a `flock` on a new file and
+/// copy what the kernel reports about them into a fixture where pid 42
+/// holds both and pid 43 shares the open file description. The device
+/// numbers differ between machines, so this can't be checked in
+#[test]
+fn fixture_finds_holders_through_fdinfo() {
+    let dir = temp_dir().join(format!("getpid-locks-{}", std::process::id()));
+    let root = dir.join("proc");
+    create_dir_all(&root).unwrap();
+    let path = dir.join("locked");
+    File::create(&path).unwrap();
+    let ofd = OpenOptions::new().read(true).write(true).open(&path).unwrap();
+    let shared = File::open(&path).unwrap();
+    let mut range: libc::flock = unsafe { std::mem::zeroed() };
+    range.l_type = libc::F_WRLCK as i16;
+    range.l_whence = libc::SEEK_SET as i16;
+    assert_eq!(unsafe { libc::fcntl(ofd.as_raw_fd(), libc::F_OFD_SETLK, &range) }, 0);
+    assert_eq!(unsafe { libc::flock(shared.as_raw_fd(), libc::LOCK_SH) }, 0);
+
+    let inode = format!(":{}", metadata(&path).unwrap().ino());
+    let locks: String = read_to_string("/proc/locks").unwrap().lines()
+        .filter(|l| l.split_whitespace().any(|f| f.ends_with(&inode)))
+        .map(|l| format!("{}\n", l))
+        .collect();
+    write(root.join("locks"), locks).unwrap();
+    let process = |pid: &str, fds: &[i32]| {
+        let base = root.join(pid);
+        create_dir_all(base.join("fdinfo")).unwrap();
+        write(base.join("comm"), "fakesvc\n").unwrap();
+        for (i, fd) in fds.iter().enumerate() {
+            copy(format!("/proc/self/fdinfo/{}", fd), base.join("fdinfo").join((i + 3).to_string())).unwrap();
+        }
+    };
+    process("42", &[ofd.as_raw_fd(), shared.as_raw_fd()]);
+    process("43", &[ofd.as_raw_fd()]);
+
+    let matcher = Matcher::new(Pattern::new("fakesvc", Mode::Exact, false).unwrap())
+        .lock(Target::new(&path, false).unwrap());
+    let found = find_in(&Fixture::new(&root), &matcher);
+    remove_dir_all(&dir).unwrap();
+    let held: Vec<(usize, Vec<(LockKind, LockMode)>)> = found.unwrap().iter()
+        .map(|m| (m.process.pid, m.process.locks.as_ref().unwrap().iter().map(|l| (l.kind, l.mode)).collect()))
+        .collect();
+    assert_eq!(held, vec![
+        (42, vec![(LockKind::Ofd, LockMode::Write), (LockKind::Flock, LockMode::Read)]),
+        (43, vec![(LockKind::Ofd, LockMode::Write)]),
+    ]);
+}
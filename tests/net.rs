@@ -0,0 +1,70 @@
+// /proc/net writes each 32 bit word of an address in host byte order,
+// these lines were read on a little endian machine
+#![cfg(target_endian = "little")]
+
+extern crate getpid;
+
+use std::net::SocketAddr;
+
+use getpid::{parse_sockets, Protocol, Socket};
+
+const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:BC8F 00000000:0000 0A 00000000:00000000 00:00000000 00000000 65534        0 966 1 00000000e4b67db0 100 0 0 10 0
+   1: 00000000:07E8 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 661 1 000000001d3d4ae4 100 0 0 10 0
+   2: 0100007F:AB32 0100007F:BC8F 01 00000000:00000000 02:0000145A 00000000     0        0 69627 3 00000000bec11bac 20 4 0 18 -1
+";
+
+const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: B80D0120020001000000000003000000:960C 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 72598 1 00000000898ec12e 100 0 0 10 0
+   1: 00000000000000000000000001000000:960C 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 72596 1 00000000c0a17f56 100 0 0 10 0
+";
+
+const UDP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+ 3783: 00000000000000000000000000000000:960D 00000000000000000000000000000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 72600 2 00000000106b188b 0
+";
+
+fn socket(protocol: Protocol, address: &str, inode: u64) -> Socket {
+    Socket {
+        protocol,
+        address: address.parse::<SocketAddr>().unwrap(),
+        inode,
+    }
+}
+
+#[test]
+fn tcp_listening_only() {
+    assert_eq!(parse_sockets(TCP, Protocol::Tcp), vec![
+        socket(Protocol::Tcp, "127.0.0.1:48271", 966),
+        socket(Protocol::Tcp, "0.0.0.0:2024", 661),
+    ]);
+}
+
+#[test]
+fn tcp6_word_order() {
+    assert_eq!(parse_sockets(TCP6, Protocol::Tcp), vec![
+        socket(Protocol::Tcp, "[2001:db8:1:2::3]:38412", 72598),
+        socket(Protocol::Tcp, "[::1]:38412", 72596),
+    ]);
+}
+
+#[test]
+fn udp6_unconnected() {
+    assert_eq!(parse_sockets(UDP6, Protocol::Udp), vec![
+        socket(Protocol::Udp, "[::]:38413", 72600),
+    ]);
+}
+
+#[test]
+fn state_depends_on_protocol() {
+    // 0A is listening for tcp but not a state udp sockets are in
+    assert!(parse_sockets(TCP, Protocol::Udp).is_empty());
+    assert!(parse_sockets(UDP6, Protocol::Tcp).is_empty());
+}
+
+#[test]
+fn header_and_garbage_are_skipped() {
+    assert!(parse_sockets("", Protocol::Tcp).is_empty());
+    assert!(parse_sockets(TCP.lines().next().unwrap(), Protocol::Tcp).is_empty());
+    let bad = "header\n   0: 0100007:BC8F 00000000:0000 0A 0:0 00:0 0 0 0 966\n   1: 0100007F:ZZZZ 00000000:0000 0A 0:0 00:0 0 0 0 967\n";
+    assert!(parse_sockets(bad, Protocol::Tcp).is_empty());
+}
@@ -0,0 +1,42 @@
+extern crate getpid;
+extern crate libc;
+
+use getpid::{Error, Signal};
+
+fn invalid(text: &str) -> String {
+    match text.parse::<Signal>() {
+        Err(Error::Invalid(msg)) => msg,
+        other => panic!("expected Error::Invalid for {}, found {:?}", text, other),
+    }
+}
+
+#[test]
+fn names() {
+    for text in &["TERM", "SIGTERM", "term", "SigTerm"] {
+        assert_eq!(text.parse::<Signal>().unwrap(), Signal::TERM);
+    }
+    assert_eq!("usr1".parse::<Signal>().unwrap().number(), libc::SIGUSR1);
+    assert_eq!(invalid("NOPE"), "unknown signal NOPE");
+    assert_eq!(invalid("SIG"), "unknown signal SIG");
+}
+
+#[test]
+fn numbers() {
+    let kill = "9".parse::<Signal>().unwrap();
+    assert_eq!(kill, Signal::KILL);
+    assert_eq!(kill.to_string(), "KILL");
+    // 0 only checks the process exists
+    assert_eq!("0".parse::<Signal>().unwrap().number(), 0);
+    assert_eq!(invalid("-1"), "invalid signal number -1");
+    assert_eq!(invalid("200"), "invalid signal number 200");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn real_time_signals() {
+    let max = libc::SIGRTMAX();
+    let signal = max.to_string().parse::<Signal>().unwrap();
+    assert_eq!(signal.name(), None);
+    assert_eq!(signal.to_string(), max.to_string());
+    assert_eq!(invalid(&(max + 1).to_string()), format!("invalid signal number {}", max + 1));
+}
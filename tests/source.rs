@@ -0,0 +1,126 @@
+extern crate getpid;
+
+use getpid::{find_in, select, write_processes, ArgPattern, EnvPattern, Error, Fixture, Format, InMemory, Matcher, Mode, NameSource, Pattern, Process, ProcessSource, Selection};
+
+const FIXTURE: &str = "tests/fixtures/proc";
+
+fn process(pid: usize, cmd: &str, start_time: u64) -> Process {
+    Process {
+        pid,
+        cmd: Some(cmd.to_string()),
+        full_cmd_path: Some(format!("/usr/bin/{}", cmd)),
+        arg0: Some(cmd.to_string()),
+        args: Some(vec![]),
+        start_time: Some(start_time),
+        ..Process::default()
+    }
+}
+
+fn in_memory() -> InMemory {
+    let mut source = InMemory::new(vec![process(7, "nginx", 300), process(3, "nginx", 500)]);
+    source.push(process(5, "postgres", 100));
+    source
+}
+
+fn matcher(text: &str, mode: Mode) -> Matcher {
+    Matcher::new(Pattern::new(text, mode, false).unwrap())
+}
+
+fn pids(processes: &[Process], format: Format) -> String {
+    let mut out = vec![];
+    write_processes(&mut out, processes, format).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn in_memory_selection() {
+    let source = in_memory();
+    let found = find_in(&source, &matcher("nginx", Mode::Exact)).unwrap();
+    match select(found.clone(), Selection::Only) {
+        Err(Error::Ambiguous(processes)) => assert_eq!(processes.len(), 2),
+        other => panic!("expected ambiguous, found {:?}", other),
+    }
+    let all: Vec<_> = select(found.clone(), Selection::All).unwrap().into_iter().map(|m| m.process).collect();
+    assert_eq!(pids(&all, Format::Pid), "3\n7\n");
+    let newest = select(found.clone(), Selection::Newest).unwrap();
+    assert_eq!(newest[0].process.pid, 3);
+    let oldest = select(found, Selection::Oldest).unwrap();
+    assert_eq!(oldest[0].process.pid, 7);
+}
+
+#[test]
+fn in_memory_formats() {
+    let source = in_memory();
+    let found = find_in(&source, &matcher("post*", Mode::Glob)).unwrap();
+    let processes: Vec<_> = select(found, Selection::Only).unwrap().into_iter().map(|m| m.process).collect();
+    let csv = pids(&processes, Format::Csv);
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("pid,cmd,full_cmd_path,"));
+    assert!(lines.next().unwrap().starts_with("5,postgres,/usr/bin/postgres,"));
+    assert_eq!(lines.next(), None);
+    let ndjson = pids(&processes, Format::NdJson);
+    assert!(ndjson.starts_with("{\"pid\":5,\"cmd\":\"postgres\""));
+    assert_eq!(ndjson.lines().count(), 1);
+}
+
+#[test]
+fn in_memory_no_match() {
+    let found = find_in(&in_memory(), &matcher("redis", Mode::Contains)).unwrap();
+    assert!(select(found, Selection::Only).unwrap().is_empty());
+}
+
+#[test]
+fn fixture_reads_plain_file_links() {
+    let mut processes = Fixture::new(FIXTURE).processes().unwrap();
+    processes.sort_by_key(|p| p.pid);
+    assert_eq!(processes.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![1, 42, 43, 50]);
+    let svc = &processes[1];
+    assert_eq!(svc.cmd, Some("fakesvc".to_string()));
+    assert_eq!(svc.full_cmd_path, Some("/usr/bin/fakesvc".to_string()));
+    assert_eq!(svc.arg0, Some("/usr/bin/fakesvc".to_string()));
+    assert_eq!(svc.args, Some(vec!["--config=/etc/fake.toml".to_string()]));
+    assert_eq!(svc.uid, Some(1000));
+    assert_eq!(svc.user, Some("app".to_string()));
+    assert_eq!(svc.group, Some("app".to_string()));
+    assert_eq!(svc.ppid, Some(1));
+    assert_eq!(svc.start_time, Some(5000));
+    assert!(svc.started_at.unwrap() >= 1_700_000_000);
+    assert!(svc.hidden.is_empty());
+}
+
+#[test]
+fn fixture_selection() {
+    let source = Fixture::new(FIXTURE);
+    let found = find_in(&source, &matcher("fakesvc", Mode::Exact)).unwrap();
+    let newest = select(found.clone(), Selection::Newest).unwrap();
+    assert_eq!(newest[0].process.pid, 43);
+    let first = select(found, Selection::First).unwrap();
+    assert_eq!(first[0].process.pid, 42);
+    let config = matcher("fakesvc", Mode::Exact).arg(ArgPattern::parse("config=/etc/fake.toml"));
+    let found = find_in(&source, &config).unwrap();
+    let processes: Vec<_> = select(found, Selection::Only).unwrap().into_iter().map(|m| m.process).collect();
+    assert_eq!(pids(&processes, Format::Pid), "42\n");
+}
+
+#[test]
+fn fixture_resolves_long_names() {
+    let source = Fixture::new(FIXTURE);
+    let long = matcher("very-long-name-daemon", Mode::Exact);
+    assert!(find_in(&source, &long).unwrap().is_empty());
+    let found = find_in(&source, &long.resolve(true)).unwrap();
+    let found = select(found, Selection::Only).unwrap();
+    assert_eq!(found[0].process.pid, 50);
+    assert_eq!(found[0].source, NameSource::Exe);
+    let json = pids(&[found[0].process.clone()], Format::Json);
+    assert!(json.contains("\"full_cmd_path\": \"/opt/very-long-name-daemon\""));
+}
+
+#[test]
+fn fixture_env_keeps_whitespace() {
+    let env = EnvPattern::parse("PADDED=  x ").unwrap();
+    let found = find_in(&Fixture::new(FIXTURE), &matcher("fakesvc", Mode::Exact).env(env)).unwrap();
+    let pids: Vec<usize> = found.iter().map(|m| m.process.pid).collect();
+    assert_eq!(pids, vec![42]);
+    let env = found[0].process.env.as_ref().unwrap();
+    assert_eq!(env.get("HOME").map(String::as_str), Some("/home/app"));
+}
@@ -0,0 +1,173 @@
+extern crate getpid;
+extern crate proptest;
+extern crate serde_json;
+
+use getpid::Stat;
+use proptest::prelude::*;
+use serde_json::Value;
+
+const SYSTEMD: &str = "1 (systemd) S 0 1 1 0 -1 4194560 48721 2817963 115 1283 \
+    97 186 5624 2121 20 0 1 0 13 171737088 3093 18446744073709551615 1 1 0 0 0 0 \
+    671173123 4096 1260 0 0 0 17 3 0 0 0 0 0 0 0 0 0 0 0 0 0\n";
+
+/// Build a stat line with `comm` and the fields after it
+fn line(pid: i32, comm: &str, rest: &[String]) -> String {
+    format!("{} ({}) {}\n", pid, comm, rest.join(" "))
+}
+
+/// Every field after comm, as numbers, in the order they appear in the file
+fn numbers(stat: &Stat) -> Vec<Option<String>> {
+    match serde_json::to_value(stat).unwrap() {
+        Value::Object(map) => map.into_iter()
+            .skip(3)
+            .map(|(_, v)| match v {
+                Value::Null => None,
+                other => Some(other.to_string()),
+            })
+            .collect(),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn parses_every_field() {
+    let stat = Stat::parse(SYSTEMD);
+    assert_eq!(stat.pid, Some(1));
+    assert_eq!(stat.comm, Some("systemd".to_string()));
+    assert_eq!(stat.state, Some('S'));
+    assert_eq!(stat.ppid, Some(0));
+    assert_eq!(stat.tty_nr, Some(0));
+    assert_eq!(stat.tpgid, Some(-1));
+    assert_eq!(stat.starttime, Some(13));
+    assert_eq!(stat.rsslim, Some(18446744073709551615));
+    assert_eq!(stat.exit_signal, Some(17));
+    assert_eq!(stat.processor, Some(3));
+    assert_eq!(stat.exit_code, Some(0));
+    assert!(numbers(&stat).iter().all(|n| n.is_some()));
+}
+
+#[test]
+fn comm_with_parens() {
+    let stat = Stat::parse(&SYSTEMD.replacen("(systemd)", "((sd-pam))", 1));
+    assert_eq!(stat.comm, Some("(sd-pam)".to_string()));
+    assert_eq!(stat.state, Some('S'));
+    assert_eq!(stat.starttime, Some(13));
+}
+
+#[test]
+fn comm_with_spaces_and_unbalanced_parens() {
+    let stat = Stat::parse(&SYSTEMD.replacen("(systemd)", "(a) b (c)", 1));
+    assert_eq!(stat.comm, Some("a) b (c".to_string()));
+    assert_eq!(stat.ppid, Some(0));
+    assert_eq!(stat.starttime, Some(13));
+    let stat = Stat::parse(&SYSTEMD.replacen("(systemd)", "(x) 5 6 7)", 1));
+    assert_eq!(stat.comm, Some("x) 5 6 7".to_string()));
+    assert_eq!(stat.state, Some('S'));
+}
+
+#[test]
+fn empty_comm() {
+    let stat = Stat::parse(&SYSTEMD.replacen("(systemd)", "()", 1));
+    assert_eq!(stat.comm, Some(String::new()));
+    assert_eq!(stat.state, Some('S'));
+}
+
+#[test]
+fn bad_field_only_loses_that_field() {
+    let stat = Stat::parse(&SYSTEMD.replacen(" 13 ", " soon ", 1));
+    assert_eq!(stat.starttime, None);
+    assert_eq!(stat.itrealvalue, Some(0));
+    assert_eq!(stat.vsize, Some(171737088));
+    assert_eq!(numbers(&stat).iter().filter(|n| n.is_none()).count(), 1);
+}
+
+#[test]
+fn truncated() {
+    let stat = Stat::parse("42 (short) R 1 42");
+    assert_eq!(stat.pid, Some(42));
+    assert_eq!(stat.comm, Some("short".to_string()));
+    assert_eq!(stat.state, Some('R'));
+    assert_eq!(stat.ppid, Some(1));
+    assert_eq!(stat.pgrp, Some(42));
+    assert_eq!(stat.session, None);
+    assert_eq!(stat.exit_code, None);
+}
+
+#[test]
+fn missing_comm() {
+    let stat = Stat::parse("42 short R 1 42");
+    assert_eq!(stat.pid, Some(42));
+    assert_eq!(stat.comm, None);
+    assert_eq!(stat.state, None);
+    assert_eq!(Stat::parse("42 )short( R"), Stat { pid: Some(42), ..Stat::default() });
+    assert_eq!(Stat::parse(""), Stat::default());
+}
+
+#[test]
+fn tty_names() {
+    let tty = |nr| Stat { tty_nr: Some(nr), ..Stat::default() }.tty_name();
+    assert_eq!(tty(0), None);
+    assert_eq!(tty(136 << 8 | 3), Some("pts/3".to_string()));
+    assert_eq!(tty((137 << 8) | 1), Some("pts/257".to_string()));
+    assert_eq!(tty(4 << 8 | 1), Some("tty1".to_string()));
+    assert_eq!(tty(4 << 8 | 65), Some("ttyS1".to_string()));
+    assert_eq!(tty(5 << 8 | 1), Some("console".to_string()));
+}
+
+#[test]
+fn reads_own_stat() {
+    let stat = Stat::read("/proc/self/stat").unwrap();
+    assert_eq!(stat.pid, Some(::std::process::id() as i32));
+    assert!(stat.comm.is_some());
+    assert!(stat.starttime.is_some());
+}
+
+proptest! {
+    #[test]
+    fn never_panics(text in ".*") {
+        let _ = Stat::parse(&text);
+    }
+
+    #[test]
+    fn never_panics_on_bytes(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let stat = Stat::parse(&String::from_utf8_lossy(&bytes));
+        let _ = stat.tty_name();
+    }
+
+    #[test]
+    fn round_trips(
+        pid in 0..i32::MAX,
+        comm in "[^\u{0}]{0,32}",
+        state in "[RSDZTtXIPW]",
+        fields in prop::collection::vec(0..i32::MAX, 49),
+    ) {
+        let mut rest = vec![state.clone()];
+        rest.extend(fields.iter().map(|f| f.to_string()));
+        let stat = Stat::parse(&line(pid, &comm, &rest));
+        prop_assert_eq!(stat.pid, Some(pid));
+        prop_assert_eq!(stat.comm.as_ref(), Some(&comm));
+        prop_assert_eq!(stat.state, state.chars().next());
+        let expected: Vec<_> = fields.iter().map(|f| Some(f.to_string())).collect();
+        prop_assert_eq!(numbers(&stat), expected);
+    }
+
+    #[test]
+    fn fields_fail_independently(
+        comm in "[^\u{0}]{0,32}",
+        fields in prop::collection::vec(0..i32::MAX, 49),
+        bad in 0usize..49,
+    ) {
+        let mut rest = vec!["S".to_string()];
+        rest.extend(fields.iter().map(|f| f.to_string()));
+        rest[bad + 1] = "x".to_string();
+        let stat = Stat::parse(&line(1, &comm, &rest));
+        prop_assert_eq!(stat.comm.as_ref(), Some(&comm));
+        for (i, n) in numbers(&stat).into_iter().enumerate() {
+            if i == bad {
+                prop_assert_eq!(n, None);
+            } else {
+                prop_assert_eq!(n, Some(fields[i].to_string()));
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+extern crate getpid;
+
+use getpid::{Error, Process, Template};
+
+fn process(args: &[&str]) -> Process {
+    Process {
+        pid: 42,
+        cmd: Some("fakesvc".to_string()),
+        full_cmd_path: Some("/usr/bin/fakesvc".to_string()),
+        args: Some(args.iter().map(|a| a.to_string()).collect()),
+        ..Process::default()
+    }
+}
+
+fn render(template: &str, process: &Process) -> String {
+    Template::parse(template).unwrap().render(process).unwrap()
+}
+
+fn invalid(template: &str) -> String {
+    match Template::parse(template) {
+        Err(Error::Invalid(msg)) => msg,
+        other => panic!("expected Error::Invalid for {}, found {:?}", template, other),
+    }
+}
+
+#[test]
+fn fields_and_escapes() {
+    let p = process(&[]);
+    assert_eq!(render("{pid}\\t{ cmd }\\n", &p), "42\tfakesvc\n");
+    assert_eq!(render("{exe}", &p), "/usr/bin/fakesvc");
+    assert_eq!(render("{{{pid}}}", &p), "{42}");
+    assert_eq!(render("\\q\\\\", &p), "\\q\\");
+    assert_eq!(render("[{user}]", &p), "[]");
+}
+
+#[test]
+fn unknown_and_unterminated_fields() {
+    assert!(invalid("{pid} {nope}").starts_with("unknown field {nope}"));
+    assert_eq!(invalid("{pid"), "unterminated field {pid in template");
+    assert_eq!(invalid("x {"), "unterminated field { in template");
+}
+
+#[test]
+fn args_are_shell_quoted() {
+    let p = process(&["--plain=1", "two words", "it's", "", "a\nb", "back\\slash\t'q'"]);
+    assert_eq!(
+        render("{args}", &p),
+        "--plain=1 'two words' 'it'\\''s' '' $'a\\nb' $'back\\\\slash\\t\\'q\\''"
+    );
+}
+
+#[test]
+fn control_characters_are_escaped() {
+    let mut p = process(&[]);
+    p.cmd = Some("a\tb\u{1b}[0m".to_string());
+    assert_eq!(render("{cmd}", &p), "a\\tb\\x1b[0m");
+}
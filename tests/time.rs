@@ -0,0 +1,36 @@
+extern crate getpid;
+
+use std::time::Duration;
+
+use getpid::{parse_duration, Error};
+
+#[test]
+fn units() {
+    assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+    assert_eq!(parse_duration("10s").unwrap(), Duration::from_secs(10));
+    assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+    assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+}
+
+#[test]
+fn bare_numbers_are_seconds() {
+    assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+    assert_eq!(parse_duration("0").unwrap(), Duration::from_secs(0));
+}
+
+#[test]
+fn fractions() {
+    assert_eq!(parse_duration("1.5").unwrap(), Duration::from_millis(1500));
+    assert_eq!(parse_duration("0.25m").unwrap(), Duration::from_secs(15));
+    assert_eq!(parse_duration(".5s").unwrap(), Duration::from_millis(500));
+}
+
+#[test]
+fn rejected() {
+    for text in &["", "s", "10x", "10 s", "-1s", "1.2.3s", "ms10", "1e400"] {
+        match parse_duration(text) {
+            Err(Error::Invalid(msg)) => assert_eq!(msg, format!("invalid duration {}", text)),
+            other => panic!("expected Error::Invalid for {:?}, found {:?}", text, other),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+extern crate getpid;
+
+use getpid::{write_trees, Process, Tree, TreeFormat};
+
+fn process(pid: usize, ppid: usize, arg0: &str) -> Process {
+    Process {
+        pid,
+        ppid: Some(ppid),
+        arg0: Some(arg0.to_string()),
+        args: Some(vec![]),
+        ..Process::default()
+    }
+}
+
+fn processes() -> Vec<Process> {
+    vec![
+        process(1, 0, "init"),
+        process(2, 1, "sshd"),
+        process(3, 2, "bash"),
+        process(4, 1, "cron"),
+        process(5, 3, "vim"),
+        // pid reuse while the snapshot was taken can make a loop
+        process(10, 11, "a"),
+        process(11, 10, "b"),
+    ]
+}
+
+fn pids(tree: &Tree) -> Vec<usize> {
+    let mut ret = vec![tree.process.pid];
+    for child in &tree.children {
+        ret.extend(pids(child));
+    }
+    ret
+}
+
+fn write(trees: &[Tree], format: TreeFormat) -> String {
+    let mut out = vec![];
+    write_trees(&mut out, trees, format).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn descendants() {
+    let tree = Tree::descendants(&processes(), 1).unwrap();
+    assert_eq!(pids(&tree), vec![1, 2, 3, 5, 4]);
+    assert_eq!(write(&[tree], TreeFormat::Ascii), "\
+1 init
+├── 2 sshd
+│   └── 3 bash
+│       └── 5 vim
+└── 4 cron
+");
+    assert!(Tree::descendants(&processes(), 99).is_none());
+}
+
+#[test]
+fn ancestors() {
+    let tree = Tree::ancestors(&processes(), 5).unwrap();
+    assert_eq!(pids(&tree), vec![1, 2, 3, 5]);
+    assert!(Tree::ancestors(&processes(), 99).is_none());
+}
+
+#[test]
+fn cycles_end() {
+    assert_eq!(pids(&Tree::descendants(&processes(), 10).unwrap()), vec![10, 11]);
+    assert_eq!(pids(&Tree::ancestors(&processes(), 10).unwrap()), vec![11, 10]);
+}
+
+#[test]
+fn dot_escaping() {
+    let tree = Tree::descendants(&[process(1, 0, "say \"hi\\there\"\nbye")], 1).unwrap();
+    assert_eq!(write(&[tree], TreeFormat::Dot), "\
+digraph processes {
+    \"1\" [label=\"1 say \\\"hi\\\\there\\\"\\\\nbye\"];
+}
+");
+}
+
+#[test]
+fn shared_processes_are_listed_once_in_dot() {
+    let all = processes();
+    let trees = vec![Tree::ancestors(&all, 5).unwrap(), Tree::ancestors(&all, 4).unwrap()];
+    let dot = write(&trees, TreeFormat::Dot);
+    assert_eq!(dot.matches("\"1\" [label").count(), 1);
+    assert_eq!(dot.matches("\"1\" -> \"2\"").count(), 1);
+    assert_eq!(dot.matches("\"1\" -> \"4\"").count(), 1);
+}
@@ -0,0 +1,53 @@
+extern crate getpid;
+
+use getpid::{find_in, parse_unix, Fixture, Matcher, Mode, Pattern, Role, UnixEntry, UnixTarget};
+
+const UNIX: &str = "\
+Num       RefCount Protocol Flags    Type St Inode Path
+00000000a5ec5d55: 00000003 00000000 00000000 0001 03   657
+00000000d3b696f9: 00000002 00000000 00010000 0001 01 73109 /tmp/sock dir/a  b.sock
+00000000c9a00dc5: 00000003 00000000 00000000 0001 03 73112 /tmp/sock dir/a  b.sock
+00000000a7a3f56d: 00000002 00000000 00010000 0001 01 73110 @getpid test
+00000000a1a6d933: 00000003 00000000 00000000 0001 03 73111
+";
+
+fn entry(inode: u64, accepting: bool, path: Option<&str>) -> UnixEntry {
+    UnixEntry {
+        inode,
+        accepting,
+        path: path.map(str::to_string),
+    }
+}
+
+#[test]
+fn paths_keep_their_spaces() {
+    assert_eq!(parse_unix(UNIX), vec![
+        entry(657, false, None),
+        entry(73109, true, Some("/tmp/sock dir/a  b.sock")),
+        entry(73112, false, Some("/tmp/sock dir/a  b.sock")),
+        entry(73110, true, Some("@getpid test")),
+        entry(73111, false, None),
+    ]);
+}
+
+#[test]
+fn accepting_is_one_flag_bit() {
+    // __SO_ACCEPTCON alongside other flag bits
+    let text = "header\n0000000000000000: 00000002 00000000 00010001 0001 01 1 /a\n\
+        0000000000000000: 00000002 00000000 00000001 0001 01 2 /b\n";
+    let accepting: Vec<bool> = parse_unix(text).iter().map(|e| e.accepting).collect();
+    assert_eq!(accepting, vec![true, false]);
+}
+
+#[test]
+fn fixture_listening_and_accepted() {
+    let source = Fixture::new("tests/fixtures/proc");
+    let all = Matcher::new(Pattern::new("", Mode::Contains, false).unwrap());
+    let found = find_in(&source, &all.clone().unix_socket(UnixTarget::new("/run/fake svc.sock"))).unwrap();
+    let sockets: Vec<(usize, Vec<(Role, u64)>)> = found.iter()
+        .map(|m| (m.process.pid, m.process.unix_sockets.as_ref().unwrap().iter().map(|s| (s.role, s.inode)).collect()))
+        .collect();
+    assert_eq!(sockets, vec![(42, vec![(Role::Listening, 73109)]), (43, vec![(Role::Accepted, 73112)])]);
+    let found = find_in(&source, &all.unix_socket(UnixTarget::new("@fakesvc"))).unwrap();
+    assert_eq!(found.iter().map(|m| m.process.pid).collect::<Vec<_>>(), vec![42]);
+}
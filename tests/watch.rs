@@ -0,0 +1,81 @@
+extern crate getpid;
+extern crate libc;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use getpid::{EventKind, Process, Snapshot};
+
+fn process(pid: usize, start_time: u64) -> Process {
+    Process {
+        pid,
+        cmd: Some("fakesvc".to_string()),
+        start_time: Some(start_time),
+        started_at: Some(1_700_000_000 + start_time / 100),
+        state: Some('S'),
+        ..Process::default()
+    }
+}
+
+fn events(older: Vec<Process>, newer: Vec<Process>) -> Vec<(EventKind, usize, Option<u64>)> {
+    Snapshot::new(older).diff(&Snapshot::new(newer), SystemTime::now())
+        .into_iter()
+        .map(|e| (e.event, e.process.pid, e.process.start_time))
+        .collect()
+}
+
+#[test]
+fn starts_and_exits() {
+    assert_eq!(events(vec![process(1, 10), process(2, 20)], vec![process(1, 10), process(3, 30)]), vec![
+        (EventKind::Exit, 2, Some(20)),
+        (EventKind::Start, 3, Some(30)),
+    ]);
+    assert!(events(vec![process(1, 10)], vec![process(1, 10)]).is_empty());
+}
+
+#[test]
+fn reused_pid_exits_then_starts() {
+    assert_eq!(events(vec![process(7, 100)], vec![process(7, 500)]), vec![
+        (EventKind::Exit, 7, Some(100)),
+        (EventKind::Start, 7, Some(500)),
+    ]);
+}
+
+#[test]
+fn zombies_have_exited() {
+    let mut zombie = process(5, 50);
+    zombie.state = Some('Z');
+    assert_eq!(events(vec![process(5, 50)], vec![zombie.clone()]), vec![(EventKind::Exit, 5, Some(50))]);
+    assert!(events(vec![zombie], vec![]).is_empty());
+}
+
+#[test]
+fn start_times() {
+    let now = SystemTime::now();
+    let older = Snapshot::new(vec![]);
+    let mut unknown = process(2, 0);
+    unknown.started_at = None;
+    let events = older.diff(&Snapshot::new(vec![process(1, 300), unknown]), now);
+    let secs = now.duration_since(UNIX_EPOCH).unwrap().as_secs();
+    assert_eq!(events[0].time, Some(1_700_000_003));
+    assert_eq!(events[1].time, Some(secs));
+    assert!(events.iter().all(|e| e.lifetime_ms.is_none()));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn lifetime_in_milliseconds() {
+    use std::fs::read_to_string;
+    use std::time::Duration;
+
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64;
+    let uptime: f64 = read_to_string("/proc/uptime").unwrap()
+        .split_whitespace().next().unwrap()
+        .parse().unwrap();
+    // started 2.5 seconds ago
+    let started = ((uptime - 2.5) * ticks as f64) as u64;
+    let events = Snapshot::new(vec![process(9, started)]).diff(&Snapshot::new(vec![]), SystemTime::now());
+    let lifetime = Duration::from_millis(events[0].lifetime_ms.unwrap());
+    assert!(lifetime >= Duration::from_millis(2500), "{:?}", lifetime);
+    assert!(lifetime < Duration::from_millis(3000), "{:?}", lifetime);
+    assert!(events[0].to_string().ends_with(&format!(" after {}.{:03}s", lifetime.as_secs(), lifetime.subsec_millis())));
+}